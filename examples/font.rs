@@ -83,7 +83,7 @@ async fn run(event_loop: EventLoop<()>, window: Arc<Window>) {
 
     let cell_info = info.compute_near_arcs(scale);
     let blob_arc = cell_info.encode_blob_arc();
-    let sdf_tex = blob_arc.encode_tex();
+    let sdf_tex = blob_arc.try_encode_tex().unwrap();
 
     let bbox = info.binding_box;
     // let 
@@ -82,7 +82,7 @@ async fn run(event_loop: EventLoop<()>, window: Arc<Window>) {
     let outline = ft_face.to_outline('魔');
     let cell_info = outline.compute_near_arcs(scale);
     let blob_arc = cell_info.encode_blob_arc();
-    let sdf_tex = blob_arc.encode_tex();
+    let sdf_tex = blob_arc.try_encode_tex().unwrap();
     let pxrange = 5.0;
     let sdf_tex_size = 32.0;
     // let verties = ft_face.verties(32.0, &mut [2.]);
@@ -134,6 +134,12 @@ ivec4 glyphy_vec4_to_bytes(const vec4 v)
 	return ivec4 (v * (256.0 - GLYPHY_EPSILON));
 }
 
+// [0, 1] 浮点 --> u16，用于 RGBA16 的数据纹理
+ivec4 glyphy_vec4_to_u16(const vec4 v)
+{
+	return ivec4 (floor(v * 65535.0 + 0.5));
+}
+
 // 浮点编码，变成两个 整数 
 ivec2 glyphy_float_to_two_nimbles(const float v)
 {
@@ -197,6 +203,16 @@ float glyphy_arc_wedge_signed_dist(const glyphy_arc_t a, const vec2 p)
 // 解码 arc 端点 
 glyphy_arc_endpoint_t glyphy_arc_endpoint_decode(const vec4 v, const vec2 nominal_size)
 {
+#ifdef GLYPHY_DATA_RGBA16
+	// RGBA16 数据纹理（TexInfo.data_format == Rgba16）：g、b 为 16 位坐标
+	vec2 p = v.gb;
+	float d = v.r;
+	if (d == 0.0) {
+		d = GLYPHY_INFINITY;
+	} else {
+		d = float(glyphy_vec4_to_u16(v).r - 32768) * GLYPHY_MAX_D / 32767.0;
+	}
+#else
 	vec2 p = (vec2 (glyphy_float_to_two_nimbles (v.a)) + v.gb) / 16.0;
 	float d = v.r;
 	if (d == 0.0) {
@@ -204,6 +220,7 @@ glyphy_arc_endpoint_t glyphy_arc_endpoint_decode(const vec4 v, const vec2 nomina
 	} else {
 		d = float(glyphy_float_to_byte(d) - 128) * GLYPHY_MAX_D / 127.0;
 	}
+#endif
 
 	p *= nominal_size;
 	return glyphy_arc_endpoint_t (p, d);
@@ -234,10 +251,22 @@ float glyphy_arc_extended_dist(const glyphy_arc_t a, const vec2 p)
 }
 
 line_t decode_line(const vec4 v, const vec2 nominal_size) {
-	ivec4 iv = glyphy_vec4_to_bytes(v);
-
 	line_t l;
 
+#ifdef GLYPHY_DATA_RGBA16
+	// d 和 角度 各 24 位，高 16 位在 r / b，低 8 位在 g / a
+	ivec4 iv = glyphy_vec4_to_u16(v);
+
+	float ua = float(iv.b) * 256.0 + float(iv.a);
+	float ia = ua - 8388608.0;
+	l.angle = -ia / 8388607.0 * 3.14159265358979;
+
+	float ud = float(iv.r - 32768) * 256.0 + float(iv.g);
+	float id = ud - 4194304.0;
+	float d = id / 2097151.0;
+#else
+	ivec4 iv = glyphy_vec4_to_bytes(v);
+
 	int ua = iv.b * 256 + iv.a;
 	int ia = ua - 0x8000;
 	l.angle = -float(ia) / float(0x7FFF) * 3.14159265358979;
@@ -245,6 +274,7 @@ line_t decode_line(const vec4 v, const vec2 nominal_size) {
 	int ud = (iv.r - 128) * 256 + iv.g;
 	int id = ud - 0x4000;
 	float d = float(id) / float(0x1FFF);
+#endif
 	
 	float scale = max(nominal_size.x, nominal_size.y);
 	
@@ -257,6 +287,13 @@ glyphy_index_t decode_glyphy_index(vec4 v, const vec2 nominal_size)
 {	
 	ivec4 c = glyphy_vec4_to_bytes(v);
 
+#ifdef GLYPHY_WIDE_INDEX
+	// 宽索引（TexInfo.index_format == Wide）：端点数2位 + sdf和偏移22位，存放在rgb中
+	int value = c.r + 256 * c.g + 65536 * c.b;
+	int v2 = value;
+	int num_endpoints = value / 4194304;
+	int sdf_and_offset_index = value - 4194304 * num_endpoints;
+#else
 	int value = c.r + 256 * c.g;
 
 	int v2 = value;
@@ -278,6 +315,7 @@ glyphy_index_t decode_glyphy_index(vec4 v, const vec2 nominal_size)
 		sdf_and_offset_index = 0;
 		num_endpoints += 1;
 	}
+#endif
 
 	int sdf_index = sdf_and_offset_index / int(u_info.x);
 	int offset = sdf_and_offset_index - sdf_index * int(u_info.x);
@@ -0,0 +1,305 @@
+//! SDF纹理图集
+//!
+//! 把多个`SdfInfo2`、`BlurInfo`的单独纹理按skyline算法装进一张或多张固定大小的页中，
+//! 并把在页中的偏移写回`TexInfo2`（`sdf_offset_x`、`sdf_offset_y`，`atlas_*`改为页坐标）。
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{blur::BlurInfo, utils::SdfInfo2};
+
+/// skyline中的一段，从x开始宽为width的区域已经被占用到y
+#[derive(Debug, Clone, Copy)]
+struct SkylineNode {
+    x: usize,
+    y: usize,
+    width: usize,
+}
+
+/// skyline装箱（bottom-left策略）
+#[derive(Debug, Clone)]
+pub struct Skyline {
+    width: usize,
+    height: usize,
+    nodes: Vec<SkylineNode>,
+}
+
+impl Skyline {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            nodes: vec![SkylineNode { x: 0, y: 0, width }],
+        }
+    }
+
+    /// 从第index段开始放置宽w高h的矩形，返回放置的y，放不下返回None
+    fn fit(&self, index: usize, w: usize, h: usize) -> Option<usize> {
+        let x = self.nodes[index].x;
+        if x + w > self.width {
+            return None;
+        }
+        let mut width_left = w as isize;
+        let mut y = 0;
+        let mut i = index;
+        while width_left > 0 {
+            let node = self.nodes.get(i)?;
+            y = y.max(node.y);
+            if y + h > self.height {
+                return None;
+            }
+            width_left -= node.width as isize;
+            i += 1;
+        }
+        Some(y)
+    }
+
+    /// 分配宽w高h的矩形，返回左上角坐标
+    pub fn alloc(&mut self, w: usize, h: usize) -> Option<(usize, usize)> {
+        // 选择放置后顶部最低的位置，相同时选择所在段最窄的
+        let mut best: Option<(usize, usize, usize, usize)> = None;
+        for i in 0..self.nodes.len() {
+            if let Some(y) = self.fit(i, w, h) {
+                let node = self.nodes[i];
+                let better = match best {
+                    Some((_, best_y, best_w, _)) => {
+                        y < best_y || (y == best_y && node.width < best_w)
+                    }
+                    None => true,
+                };
+                if better {
+                    best = Some((i, y, node.width, node.x));
+                }
+            }
+        }
+        let (index, y, _, x) = best?;
+
+        self.nodes.insert(
+            index,
+            SkylineNode {
+                x,
+                y: y + h,
+                width: w,
+            },
+        );
+
+        // 裁掉被新段覆盖的部分
+        let i = index + 1;
+        while i < self.nodes.len() {
+            let prev_end = self.nodes[i - 1].x + self.nodes[i - 1].width;
+            let node = &mut self.nodes[i];
+            if node.x >= prev_end {
+                break;
+            }
+            let shrink = prev_end - node.x;
+            if node.width <= shrink {
+                self.nodes.remove(i);
+            } else {
+                node.x += shrink;
+                node.width -= shrink;
+                break;
+            }
+        }
+
+        // 合并相同高度的相邻段
+        let mut i = 0;
+        while i + 1 < self.nodes.len() {
+            if self.nodes[i].y == self.nodes[i + 1].y {
+                self.nodes[i].width += self.nodes[i + 1].width;
+                self.nodes.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+        Some((x, y))
+    }
+}
+
+/// 图集的一页，单通道
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtlasPage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// 纹理在图集中的位置
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct AtlasRect {
+    /// 所在页
+    pub page: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// SDF纹理图集
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct SdfAtlas {
+    page_width: usize,
+    page_height: usize,
+    /// 纹理之间的间隔（像素）
+    padding: usize,
+    pages: Vec<AtlasPage>,
+    skylines: Vec<Skyline>,
+}
+
+impl SdfAtlas {
+    /// 放入一张单通道纹理，页放不下时新建一页
+    ///
+    /// # 参数
+    /// * `width`、`height` - 纹理的宽高
+    /// * `data` - 纹理数据，按行存储
+    ///
+    /// # 返回
+    /// * `Option<AtlasRect>` - 纹理在图集中的位置，纹理比页还大时返回None
+    pub fn pack(&mut self, width: usize, height: usize, data: &[u8]) -> Option<AtlasRect> {
+        assert_eq!(data.len(), width * height);
+        let w = width + self.padding;
+        let h = height + self.padding;
+        if w > self.page_width || h > self.page_height {
+            return None;
+        }
+
+        let mut found = None;
+        for (page, skyline) in self.skylines.iter_mut().enumerate() {
+            if let Some((x, y)) = skyline.alloc(w, h) {
+                found = Some((page, x, y));
+                break;
+            }
+        }
+        let (page, x, y) = match found {
+            Some(r) => r,
+            None => {
+                self.add_page();
+                let page = self.pages.len() - 1;
+                let (x, y) = self.skylines[page].alloc(w, h)?;
+                (page, x, y)
+            }
+        };
+
+        let target = &mut self.pages[page];
+        for row in 0..height {
+            let dst = (y + row) * self.page_width + x;
+            target.data[dst..dst + width].copy_from_slice(&data[row * width..(row + 1) * width]);
+        }
+
+        Some(AtlasRect {
+            page,
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// 放入SDF纹理，并把偏移写回`tex_info`，`atlas_*`变为页坐标
+    pub fn add_sdf(&mut self, info: &mut SdfInfo2) -> Option<AtlasRect> {
+        let rect = self.pack(
+            info.tex_width as usize,
+            info.tex_height as usize,
+            &info.sdf_tex,
+        )?;
+        let tex_info = &mut info.tex_info;
+        tex_info.sdf_offset_x = rect.x;
+        tex_info.sdf_offset_y = rect.y;
+        tex_info.atlas_min_x += rect.x as f32;
+        tex_info.atlas_min_y += rect.y as f32;
+        tex_info.atlas_max_x += rect.x as f32;
+        tex_info.atlas_max_y += rect.y as f32;
+        Some(rect)
+    }
+
+    /// 放入模糊纹理，`bbox`变为页坐标
+    pub fn add_blur(&mut self, info: &mut BlurInfo) -> Option<AtlasRect> {
+        let rect = self.pack(info.width, info.height, &info.tex)?;
+        info.bbox[0] += rect.x as f32;
+        info.bbox[1] += rect.y as f32;
+        info.bbox[2] += rect.x as f32;
+        info.bbox[3] += rect.y as f32;
+        Some(rect)
+    }
+
+    pub fn pages(&self) -> &[AtlasPage] {
+        &self.pages
+    }
+
+    pub fn into_pages(self) -> Vec<AtlasPage> {
+        self.pages
+    }
+
+    fn add_page(&mut self) {
+        self.pages.push(AtlasPage {
+            width: self.page_width as u32,
+            height: self.page_height as u32,
+            data: vec![0; self.page_width * self.page_height],
+        });
+        self.skylines
+            .push(Skyline::new(self.page_width, self.page_height));
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl SdfAtlas {
+    /// 创建图集
+    ///
+    /// # 参数
+    /// * `page_width`、`page_height` - 每页的大小
+    /// * `padding` - 纹理之间的间隔（像素）
+    pub fn new(page_width: usize, page_height: usize, padding: usize) -> Self {
+        Self {
+            page_width,
+            page_height,
+            padding,
+            pages: vec![],
+            skylines: vec![],
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// 获取某一页的纹理数据
+    pub fn page_data(&self, page: usize) -> Vec<u8> {
+        self.pages[page].data.clone()
+    }
+
+    /// 通过 wasm 绑定放入SDF纹理
+    ///
+    /// # 参数
+    /// * `info` - 序列化后的SdfInfo2
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`(AtlasRect, TexInfo2)`，放不下时为空
+    pub fn add_sdf_of_wasm(&mut self, info: &[u8]) -> Vec<u8> {
+        let mut info: SdfInfo2 = bitcode::deserialize(info).unwrap();
+        match self.add_sdf(&mut info) {
+            Some(rect) => bitcode::serialize(&(rect, info.tex_info)).unwrap(),
+            None => vec![],
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let mut atlas = SdfAtlas::new(64, 64, 2);
+    let tex = vec![255; 30 * 30];
+    let r1 = atlas.pack(30, 30, &tex).unwrap();
+    let r2 = atlas.pack(30, 30, &tex).unwrap();
+    let r3 = atlas.pack(30, 30, &tex).unwrap();
+    assert_eq!((r1.page, r1.x, r1.y), (0, 0, 0));
+    assert_eq!((r2.page, r2.x, r2.y), (0, 32, 0));
+    assert_eq!((r3.page, r3.x, r3.y), (0, 0, 32));
+    // 第一页放满后新建一页
+    atlas.pack(30, 30, &tex).unwrap();
+    let r5 = atlas.pack(30, 30, &tex).unwrap();
+    assert_eq!(r5.page, 1);
+    // 间隔保持为0
+    assert_eq!(atlas.pages()[0].data[30], 0);
+    assert_eq!(atlas.pages()[0].data[32], 255);
+}
@@ -0,0 +1,319 @@
+//! 离线烘焙SDF图集
+//!
+//! 读取`sdf.json`格式的配置（`[{path, family_name, text}]`），为其中的字符生成SDF纹理，
+//! 装进`SdfAtlas`后把每一页写成png，并输出每个字符的度量信息（json），供构建流程预先生成文字纹理。
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use pi_share::Share;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atlas::{AtlasPage, SdfAtlas},
+    export::texture::{write_png, TexFormat, TexView},
+    font::FontFace,
+    utils::SCALE,
+};
+
+/// 配置中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BakeEntry {
+    /// 字体文件路径，相对路径相对于配置文件所在目录
+    pub path: String,
+    pub family_name: String,
+    /// 需要烘焙的文字
+    pub text: String,
+}
+
+/// 烘焙参数
+#[derive(Debug, Clone)]
+pub struct BakeOptions {
+    /// 单个字符的纹理大小，设置`px_per_em`时不使用
+    pub tex_size: usize,
+    pub pxrange: u32,
+    /// 字符纹理四周留出的像素
+    pub cur_off: u32,
+    pub page_width: usize,
+    pub page_height: usize,
+    /// 字符纹理之间的间隔（像素）
+    pub padding: usize,
+    /// 固定的每em像素数，所有字符按同一比例生成，纹理宽高随字形变化；
+    /// 为None时每个字符单独缩放，较长边填满`tex_size`。导出BMFont时需要设置
+    pub px_per_em: Option<f32>,
+}
+
+impl Default for BakeOptions {
+    fn default() -> Self {
+        Self {
+            tex_size: 32,
+            pxrange: 5,
+            cur_off: 5,
+            page_width: 1024,
+            page_height: 1024,
+            padding: 1,
+            px_per_em: None,
+        }
+    }
+}
+
+/// 单个字符的度量信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlyphMetrics {
+    pub family_name: String,
+    pub char: char,
+    /// 所在页
+    pub page: usize,
+    /// 水平推进量（em）
+    pub advance: f32,
+    /// 平面范围（em）[min_x, min_y, max_x, max_y]
+    pub plane_bounds: [f32; 4],
+    /// 页中的范围（像素）[min_x, min_y, max_x, max_y]
+    pub atlas_bounds: [f32; 4],
+}
+
+/// 烘焙结果
+#[derive(Debug, Clone)]
+pub struct BakeResult {
+    pub pages: Vec<AtlasPage>,
+    pub glyphs: Vec<GlyphMetrics>,
+}
+
+/// 写入度量文件的内容
+#[derive(Serialize)]
+struct BakeMetrics<'a> {
+    tex_size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    px_per_em: Option<f32>,
+    pxrange: u32,
+    page_width: usize,
+    page_height: usize,
+    pages: Vec<String>,
+    glyphs: &'a [GlyphMetrics],
+}
+
+/// 读取配置文件，把字体路径转为相对于配置文件目录的路径
+pub fn load_config(path: impl AsRef<Path>) -> io::Result<Vec<BakeEntry>> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    let mut entries: Vec<BakeEntry> = serde_json::from_slice(&data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(dir) = path.parent() {
+        for entry in &mut entries {
+            if Path::new(&entry.path).is_relative() {
+                entry.path = dir.join(&entry.path).to_string_lossy().into_owned();
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// 烘焙配置中的所有字符
+///
+/// 同一字体族中重复的字符只生成一次，空白字符没有轮廓，字体（包括默认字体）中没有的字符会打印警告，这两种都会被跳过。
+///
+/// # 参数
+/// * `entries` - 配置项
+/// * `options` - 烘焙参数
+///
+/// # 返回
+/// * `io::Result<BakeResult>` - 图集页和字符度量，字体文件读取失败或字符纹理比页还大时返回错误
+pub fn bake(entries: &[BakeEntry], options: &BakeOptions) -> io::Result<BakeResult> {
+    let mut faces: HashMap<PathBuf, FontFace> = HashMap::new();
+    let mut baked = HashSet::new();
+    let mut atlas = SdfAtlas::new(options.page_width, options.page_height, options.padding);
+    let mut glyphs = vec![];
+
+    for entry in entries {
+        let path = PathBuf::from(&entry.path);
+        if !faces.contains_key(&path) {
+            let data = fs::read(&path)?;
+            faces.insert(path.clone(), FontFace::new(Share::new(data)));
+        }
+        let face = faces.get_mut(&path).unwrap();
+
+        for ch in entry.text.chars() {
+            if ch.is_whitespace() || !baked.insert((entry.family_name.clone(), ch)) {
+                continue;
+            }
+            if face.glyph_index(ch) == 0 {
+                log::warn!("bake: '{}' not found in {}, skipped", ch, entry.path);
+                continue;
+            }
+            let outline = face.to_outline(ch);
+            let mut info = match options.px_per_em {
+                Some(px_per_em) => outline.compute_sdf_tex_px_per_em(
+                    outline.compute_near_arcs_rect(2.0),
+                    px_per_em,
+                    options.pxrange,
+                    false,
+                    options.cur_off,
+                ),
+                None => outline.compute_sdf_tex(
+                    outline.compute_near_arcs(2.0),
+                    options.tex_size,
+                    options.pxrange,
+                    false,
+                    options.cur_off,
+                ),
+            };
+            let rect = atlas.add_sdf(&mut info).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("glyph '{}' is larger than the atlas page", ch),
+                )
+            })?;
+            let t = &info.tex_info;
+            // plane_*为轮廓坐标（每em为SCALE）除以units_per_em，换算为em
+            let to_em = outline.units_per_em as f32 / SCALE;
+            glyphs.push(GlyphMetrics {
+                family_name: entry.family_name.clone(),
+                char: ch,
+                page: rect.page,
+                // 轮廓中的advance是取整后的em，这里重新查询
+                advance: face.horizontal_advance(ch),
+                plane_bounds: [
+                    t.plane_min_x * to_em,
+                    t.plane_min_y * to_em,
+                    t.plane_max_x * to_em,
+                    t.plane_max_y * to_em,
+                ],
+                atlas_bounds: [t.atlas_min_x, t.atlas_min_y, t.atlas_max_x, t.atlas_max_y],
+            });
+        }
+    }
+
+    Ok(BakeResult {
+        pages: atlas.into_pages(),
+        glyphs,
+    })
+}
+
+/// 把烘焙结果写入目录：`{name}_{页号}.png`和`{name}.json`
+pub fn write_output(
+    result: &BakeResult,
+    options: &BakeOptions,
+    out_dir: impl AsRef<Path>,
+    name: &str,
+) -> io::Result<()> {
+    let out_dir = out_dir.as_ref();
+    fs::create_dir_all(out_dir)?;
+
+    let mut pages = vec![];
+    for (i, page) in result.pages.iter().enumerate() {
+        let file = format!("{}_{}.png", name, i);
+        write_png(
+            out_dir.join(&file),
+            &TexView {
+                data: &page.data,
+                width: page.width,
+                height: page.height,
+                format: TexFormat::R8,
+                y_up: false,
+            },
+        )?;
+        pages.push(file);
+    }
+
+    let metrics = BakeMetrics {
+        tex_size: options.tex_size,
+        px_per_em: options.px_per_em,
+        pxrange: options.pxrange,
+        page_width: options.page_width,
+        page_height: options.page_height,
+        pages,
+        glyphs: &result.glyphs,
+    };
+    let json = serde_json::to_vec_pretty(&metrics)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    fs::write(out_dir.join(format!("{}.json", name)), json)
+}
+
+#[test]
+fn test() {
+    let entries = vec![BakeEntry {
+        path: concat!(env!("CARGO_MANIFEST_DIR"), "/source/SOURCEHANSANSK-MEDIUM.TTF").to_string(),
+        family_name: "MEDIUM".to_string(),
+        text: "魔 A魔".to_string(),
+    }];
+    let options = BakeOptions::default();
+    let result = bake(&entries, &options).unwrap();
+    // 空白字符和重复的字符被跳过
+    assert_eq!(result.glyphs.iter().map(|g| g.char).collect::<String>(), "魔A");
+    assert_eq!(result.pages.len(), 1);
+
+    // 平面范围和推进量都以em为单位
+    for g in &result.glyphs {
+        let [min_x, min_y, max_x, max_y] = g.plane_bounds;
+        assert!(min_x > -0.2 && max_x < g.advance + 0.2, "{:?}", g);
+        assert!(min_y > -0.3 && max_y < 1.2, "{:?}", g);
+        assert!(max_x - min_x > 0.3 && max_y - min_y > 0.3, "{:?}", g);
+    }
+    assert_eq!(result.glyphs[0].advance, 1.0);
+    assert!(result.glyphs[1].advance > 0.5 && result.glyphs[1].advance < 1.0);
+
+    let dir = std::env::temp_dir().join("pi_sdf_bake_test");
+    write_output(&result, &options, &dir, "test").unwrap();
+    assert!(dir.join("test_0.png").exists());
+    let json: serde_json::Value = serde_json::from_slice(&fs::read(dir.join("test.json")).unwrap()).unwrap();
+    assert_eq!(json["glyphs"].as_array().unwrap().len(), 2);
+    assert_eq!(json["pages"][0], "test_0.png");
+}
+
+#[test]
+fn test_px_per_em() {
+    use crate::export::{
+        bmfont::to_bmfont_text,
+        ExportFont, ExportGlyph,
+    };
+
+    let entries = vec![BakeEntry {
+        path: concat!(env!("CARGO_MANIFEST_DIR"), "/source/SOURCEHANSANSK-MEDIUM.TTF").to_string(),
+        family_name: "MEDIUM".to_string(),
+        text: "魔A-".to_string(),
+    }];
+    let export = |result: &BakeResult| ExportFont {
+        name: "MEDIUM".to_string(),
+        size: 32.0,
+        distance_range: 5.0,
+        line_height: 1.25,
+        ascender: 1.0,
+        descender: -0.25,
+        page_width: 1024,
+        page_height: 1024,
+        pages: vec!["test_0.png".to_string()],
+        glyphs: result
+            .glyphs
+            .iter()
+            .map(|g| ExportGlyph {
+                char: g.char,
+                page: g.page,
+                advance: g.advance,
+                plane_bounds: Some(g.plane_bounds),
+                atlas_bounds: Some(g.atlas_bounds),
+            })
+            .collect(),
+        kerning: vec![],
+    };
+
+    // 每个字符单独缩放，宽高比不同的字符像素/em不同
+    let result = bake(&entries, &BakeOptions::default()).unwrap();
+    assert!(to_bmfont_text(&export(&result)).is_err());
+
+    let options = BakeOptions {
+        px_per_em: Some(32.0),
+        ..Default::default()
+    };
+    let result = bake(&entries, &options).unwrap();
+    for g in &result.glyphs {
+        let [l, b, r, t] = g.plane_bounds;
+        let [x0, y0, x1, y1] = g.atlas_bounds;
+        assert!(((x1 - x0) - (r - l) * 32.0).abs() < 1e-2, "{:?}", g);
+        assert!(((y1 - y0) - (t - b) * 32.0).abs() < 1e-2, "{:?}", g);
+    }
+    let text = to_bmfont_text(&export(&result)).unwrap();
+    assert_eq!(text.matches("char id=").count(), 3);
+    assert!(text.contains("char id=39764 ") && text.contains("xadvance=32 "));
+}
@@ -0,0 +1,21 @@
+//! 离线烘焙SDF图集
+//!
+//! 用法：`sdf_bake [配置文件，默认sdf.json] [输出目录，默认当前目录]`
+
+use pi_sdf::bake::{bake, load_config, write_output, BakeOptions};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let config = args.next().unwrap_or_else(|| "sdf.json".to_string());
+    let out_dir = args.next().unwrap_or_else(|| ".".to_string());
+
+    let options = BakeOptions::default();
+    let entries = load_config(&config).expect("failed to read config");
+    let result = bake(&entries, &options).expect("failed to bake");
+    write_output(&result, &options, &out_dir, "sdf_atlas").expect("failed to write output");
+    println!(
+        "baked {} glyphs into {} pages",
+        result.glyphs.len(),
+        result.pages.len()
+    );
+}
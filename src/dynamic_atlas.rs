@@ -0,0 +1,309 @@
+//! 运行时动态字符图集
+//!
+//! 用于无法预先烘焙的文字（聊天、用户输入等）。图集按固定大小的格子划分，字符按需放入空闲格子，
+//! 放满后淘汰最久未使用的字符。每次写入只标记对应格子为脏，渲染端通过`take_dirty_rects`只上传变化的区域。
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::utils::{SdfInfo2, TexInfo2};
+
+/// 字符在图集中的键
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlyphKey {
+    /// 字体的标识，由调用方分配
+    pub face: u32,
+    pub glyph_id: u32,
+    pub tex_size: u32,
+    pub pxrange: u32,
+}
+
+/// 需要重新上传的区域及其像素数据
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// 区域内的像素，按行存储
+    pub data: Vec<u8>,
+}
+
+/// 一个格子中存放的字符
+#[derive(Debug, Clone)]
+struct Slot {
+    key: GlyphKey,
+    /// 偏移已改为图集坐标的字符信息
+    tex_info: TexInfo2,
+    /// 最后一次使用的时间
+    last_use: u64,
+}
+
+/// 带LRU淘汰的动态字符图集，单通道
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct DynamicAtlas {
+    width: usize,
+    height: usize,
+    slot_width: usize,
+    slot_height: usize,
+    data: Vec<u8>,
+    slots: Vec<Option<Slot>>,
+    map: HashMap<GlyphKey, usize>,
+    tick: u64,
+    /// 写入过的格子，按写入顺序，不重复
+    dirty: Vec<usize>,
+    /// 被淘汰的字符
+    evicted: Vec<GlyphKey>,
+}
+
+impl DynamicAtlas {
+    /// 查找已在图集中的字符，并更新其使用时间
+    pub fn get(&mut self, key: &GlyphKey) -> Option<&TexInfo2> {
+        let index = *self.map.get(key)?;
+        self.tick += 1;
+        let slot = self.slots[index].as_mut().unwrap();
+        slot.last_use = self.tick;
+        Some(&slot.tex_info)
+    }
+
+    /// 放入字符的SDF纹理，已存在时直接返回，图集满时淘汰最久未使用的字符
+    ///
+    /// # 参数
+    /// * `key` - 字符的键
+    /// * `info` - `compute_sdf_tex`等得到的SDF纹理
+    ///
+    /// # 返回
+    /// * `Option<TexInfo2>` - 偏移和`atlas_*`为图集坐标的字符信息，纹理比格子大时返回None
+    pub fn insert(&mut self, key: GlyphKey, info: &SdfInfo2) -> Option<TexInfo2> {
+        if let Some(tex_info) = self.get(&key) {
+            return Some(tex_info.clone());
+        }
+        let (w, h) = (info.tex_width as usize, info.tex_height as usize);
+        if w > self.slot_width || h > self.slot_height {
+            return None;
+        }
+
+        let index = match self.slots.iter().position(|s| s.is_none()) {
+            Some(index) => index,
+            None => {
+                let index = self
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, s)| s.as_ref().unwrap().last_use)
+                    .map(|(i, _)| i)?;
+                let old = self.slots[index].take().unwrap();
+                self.map.remove(&old.key);
+                self.evicted.push(old.key);
+                index
+            }
+        };
+
+        let (x, y) = self.slot_origin(index);
+        for row in 0..self.slot_height {
+            let dst = (y + row) * self.width + x;
+            let line = &mut self.data[dst..dst + self.slot_width];
+            if row < h {
+                line[..w].copy_from_slice(&info.sdf_tex[row * w..(row + 1) * w]);
+                line[w..].fill(0);
+            } else {
+                // 清掉被淘汰字符残留的像素
+                line.fill(0);
+            }
+        }
+
+        let mut tex_info = info.tex_info.clone();
+        tex_info.sdf_offset_x = x;
+        tex_info.sdf_offset_y = y;
+        tex_info.atlas_min_x += x as f32;
+        tex_info.atlas_min_y += y as f32;
+        tex_info.atlas_max_x += x as f32;
+        tex_info.atlas_max_y += y as f32;
+
+        self.tick += 1;
+        self.slots[index] = Some(Slot {
+            key,
+            tex_info: tex_info.clone(),
+            last_use: self.tick,
+        });
+        self.map.insert(key, index);
+        if !self.dirty.contains(&index) {
+            self.dirty.push(index);
+        }
+        Some(tex_info)
+    }
+
+    /// 取出自上次调用以来变化的区域
+    pub fn take_dirty_rects(&mut self) -> Vec<DirtyRect> {
+        let dirty = std::mem::take(&mut self.dirty);
+        dirty
+            .into_iter()
+            .map(|index| {
+                let (x, y) = self.slot_origin(index);
+                let mut data = Vec::with_capacity(self.slot_width * self.slot_height);
+                for row in 0..self.slot_height {
+                    let src = (y + row) * self.width + x;
+                    data.extend_from_slice(&self.data[src..src + self.slot_width]);
+                }
+                DirtyRect {
+                    x: x as u32,
+                    y: y as u32,
+                    width: self.slot_width as u32,
+                    height: self.slot_height as u32,
+                    data,
+                }
+            })
+            .collect()
+    }
+
+    /// 取出自上次调用以来被淘汰的字符，调用方需让引用它们的文字重新排版
+    pub fn take_evicted(&mut self) -> Vec<GlyphKey> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// 整张图集的像素数据
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn slot_origin(&self, index: usize) -> (usize, usize) {
+        let columns = self.width / self.slot_width;
+        (
+            index % columns * self.slot_width,
+            index / columns * self.slot_height,
+        )
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl DynamicAtlas {
+    /// 创建动态图集
+    ///
+    /// # 参数
+    /// * `width`、`height` - 图集的大小
+    /// * `slot_width`、`slot_height` - 每个格子的大小，需不小于字符纹理（`tex_size + 2 * cur_off`）
+    pub fn new(width: usize, height: usize, slot_width: usize, slot_height: usize) -> Self {
+        assert!(slot_width > 0 && slot_height > 0);
+        let count = (width / slot_width) * (height / slot_height);
+        Self {
+            width,
+            height,
+            slot_width,
+            slot_height,
+            data: vec![0; width * height],
+            slots: vec![None; count],
+            map: HashMap::new(),
+            tick: 0,
+            dirty: vec![],
+            evicted: vec![],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// 格子的总数
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// 已放入的字符数
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// 通过 wasm 绑定查找字符
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`TexInfo2`，不在图集中时为空
+    pub fn get_of_wasm(&mut self, key: GlyphKey) -> Vec<u8> {
+        match self.get(&key) {
+            Some(tex_info) => bitcode::serialize(tex_info).unwrap(),
+            None => vec![],
+        }
+    }
+
+    /// 通过 wasm 绑定放入字符
+    ///
+    /// # 参数
+    /// * `key` - 字符的键
+    /// * `info` - 序列化后的SdfInfo2
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`TexInfo2`，纹理比格子大时为空
+    pub fn insert_of_wasm(&mut self, key: GlyphKey, info: &[u8]) -> Vec<u8> {
+        let info: SdfInfo2 = bitcode::deserialize(info).unwrap();
+        match self.insert(key, &info) {
+            Some(tex_info) => bitcode::serialize(&tex_info).unwrap(),
+            None => vec![],
+        }
+    }
+
+    /// 通过 wasm 绑定取出变化的区域
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`Vec<DirtyRect>`
+    pub fn take_dirty_rects_of_wasm(&mut self) -> Vec<u8> {
+        bitcode::serialize(&self.take_dirty_rects()).unwrap()
+    }
+
+    /// 通过 wasm 绑定取出被淘汰的字符
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`Vec<GlyphKey>`
+    pub fn take_evicted_of_wasm(&mut self) -> Vec<u8> {
+        bitcode::serialize(&self.take_evicted()).unwrap()
+    }
+}
+
+#[test]
+fn test() {
+    let info = |v: u8| SdfInfo2 {
+        tex_info: TexInfo2::default(),
+        sdf_tex: vec![v; 12 * 12],
+        tex_size: 12,
+        tex_width: 12,
+        tex_height: 12,
+        inner_range: 2.0,
+        outer_range: 2.0,
+    };
+    let key = |glyph_id| GlyphKey {
+        face: 0,
+        glyph_id,
+        tex_size: 8,
+        pxrange: 2,
+    };
+    // 2x2个格子
+    let mut atlas = DynamicAtlas::new(32, 32, 16, 16);
+    for i in 0..4 {
+        atlas.insert(key(i), &info(i as u8 + 1)).unwrap();
+    }
+    assert_eq!(atlas.take_dirty_rects().len(), 4);
+    // 使用0后，最久未使用的是1
+    atlas.get(&key(0)).unwrap();
+    let t = atlas.insert(key(4), &info(5)).unwrap();
+    assert_eq!((t.sdf_offset_x, t.sdf_offset_y), (16, 0));
+    assert_eq!(atlas.take_evicted(), vec![key(1)]);
+    assert!(atlas.get(&key(1)).is_none());
+
+    let dirty = atlas.take_dirty_rects();
+    assert_eq!(dirty.len(), 1);
+    assert_eq!((dirty[0].x, dirty[0].y), (16, 0));
+    assert_eq!(dirty[0].data[0], 5);
+    // 格子中字符纹理以外的部分被清零
+    assert_eq!(dirty[0].data[12], 0);
+    assert!(atlas.take_dirty_rects().is_empty());
+}
@@ -0,0 +1,225 @@
+//! BMFont/AngelCode格式
+//!
+//! BMFont以像素描述字符，所有字符共用`size`这一个比例，纹理区域按1:1画到屏幕上；
+//! 字符的纹理区域直接取图集中的范围，偏移和推进量由em换算为`size`下的像素。
+//! 所以只有每个字符在图集中的像素/em都等于`size`时才能导出，
+//! 每个字符单独缩放（较长边填满纹理）的图集会返回`NonUniformScaleError`。
+//! 需要导出时用`OutlineInfo::compute_sdf_tex_px_per_em`生成纹理（烘焙时设置`BakeOptions::px_per_em`），`size`取相同的值。
+use std::fmt::Write;
+
+use super::{ExportFont, ExportGlyph};
+
+/// 字符在图集中的像素/em与`ExportFont.size`不一致，BMFont无法表示
+#[derive(Debug, Clone)]
+pub struct NonUniformScaleError {
+    /// 第一个不一致的字符
+    pub char: char,
+    /// 该字符在图集中的像素/em：[水平, 竖直]
+    pub px_per_em: [f32; 2],
+    /// 字体的像素/em
+    pub size: f32,
+}
+
+/// 字符在BMFont中的像素信息：x, y, width, height, xoffset, yoffset, xadvance
+fn char_rect(font: &ExportFont, glyph: &ExportGlyph) -> Result<[i32; 7], NonUniformScaleError> {
+    let xadvance = (glyph.advance * font.size).round() as i32;
+    let (plane, atlas) = match (glyph.plane_bounds, glyph.atlas_bounds) {
+        (Some(plane), Some(atlas)) => (plane, atlas),
+        _ => return Ok([0, 0, 0, 0, 0, 0, xadvance]),
+    };
+
+    // 图集范围取整到像素，允许1像素的误差
+    let (plane_w, plane_h) = (plane[2] - plane[0], plane[3] - plane[1]);
+    let (atlas_w, atlas_h) = (atlas[2] - atlas[0], atlas[3] - atlas[1]);
+    if (atlas_w - plane_w * font.size).abs() > 1.0
+        || (atlas_h - plane_h * font.size).abs() > 1.0
+    {
+        return Err(NonUniformScaleError {
+            char: glyph.char,
+            px_per_em: [atlas_w / plane_w, atlas_h / plane_h],
+            size: font.size,
+        });
+    }
+
+    Ok([
+        atlas[0].round() as i32,
+        atlas[1].round() as i32,
+        atlas_w.round() as i32,
+        atlas_h.round() as i32,
+        (plane[0] * font.size).round() as i32,
+        // yoffset为从行顶部（基线之上ascender处）到字符顶部的距离
+        ((font.ascender - plane[3]) * font.size).round() as i32,
+        xadvance,
+    ])
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// 导出为BMFont文本格式（.fnt）
+///
+/// # 返回
+/// * `Result<String, NonUniformScaleError>` - 文本；有字符的像素/em与`size`不一致时返回错误
+pub fn to_bmfont_text(font: &ExportFont) -> Result<String, NonUniformScaleError> {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "info face=\"{}\" size={} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=1 aa=1 padding=0,0,0,0 spacing=0,0",
+        font.name.replace('"', ""),
+        font.size.round() as i32
+    );
+    let _ = writeln!(
+        out,
+        "common lineHeight={} base={} scaleW={} scaleH={} pages={} packed=0",
+        (font.line_height * font.size).round() as i32,
+        (font.ascender * font.size).round() as i32,
+        font.page_width,
+        font.page_height,
+        font.pages.len()
+    );
+    for (i, file) in font.pages.iter().enumerate() {
+        let _ = writeln!(out, "page id={} file=\"{}\"", i, file);
+    }
+    let _ = writeln!(out, "chars count={}", font.glyphs.len());
+    for glyph in &font.glyphs {
+        let [x, y, w, h, xoffset, yoffset, xadvance] = char_rect(font, glyph)?;
+        let _ = writeln!(
+            out,
+            "char id={} x={} y={} width={} height={} xoffset={} yoffset={} xadvance={} page={} chnl=15",
+            glyph.char as u32, x, y, w, h, xoffset, yoffset, xadvance, glyph.page
+        );
+    }
+    let _ = writeln!(out, "kernings count={}", font.kerning.len());
+    for pair in &font.kerning {
+        let _ = writeln!(
+            out,
+            "kerning first={} second={} amount={}",
+            pair.left as u32,
+            pair.right as u32,
+            (pair.advance * font.size).round() as i32
+        );
+    }
+    Ok(out)
+}
+
+/// 导出为BMFont XML格式
+///
+/// # 返回
+/// * `Result<String, NonUniformScaleError>` - XML；有字符的像素/em与`size`不一致时返回错误
+pub fn to_bmfont_xml(font: &ExportFont) -> Result<String, NonUniformScaleError> {
+    let mut out = String::new();
+    let _ = writeln!(out, "<?xml version=\"1.0\"?>");
+    let _ = writeln!(out, "<font>");
+    let _ = writeln!(
+        out,
+        "  <info face=\"{}\" size=\"{}\" bold=\"0\" italic=\"0\" charset=\"\" unicode=\"1\" stretchH=\"100\" smooth=\"1\" aa=\"1\" padding=\"0,0,0,0\" spacing=\"0,0\"/>",
+        escape_xml(&font.name),
+        font.size.round() as i32
+    );
+    let _ = writeln!(
+        out,
+        "  <common lineHeight=\"{}\" base=\"{}\" scaleW=\"{}\" scaleH=\"{}\" pages=\"{}\" packed=\"0\"/>",
+        (font.line_height * font.size).round() as i32,
+        (font.ascender * font.size).round() as i32,
+        font.page_width,
+        font.page_height,
+        font.pages.len()
+    );
+    let _ = writeln!(out, "  <pages>");
+    for (i, file) in font.pages.iter().enumerate() {
+        let _ = writeln!(out, "    <page id=\"{}\" file=\"{}\"/>", i, escape_xml(file));
+    }
+    let _ = writeln!(out, "  </pages>");
+    let _ = writeln!(out, "  <chars count=\"{}\">", font.glyphs.len());
+    for glyph in &font.glyphs {
+        let [x, y, w, h, xoffset, yoffset, xadvance] = char_rect(font, glyph)?;
+        let _ = writeln!(
+            out,
+            "    <char id=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" xoffset=\"{}\" yoffset=\"{}\" xadvance=\"{}\" page=\"{}\" chnl=\"15\"/>",
+            glyph.char as u32, x, y, w, h, xoffset, yoffset, xadvance, glyph.page
+        );
+    }
+    let _ = writeln!(out, "  </chars>");
+    let _ = writeln!(out, "  <kernings count=\"{}\">", font.kerning.len());
+    for pair in &font.kerning {
+        let _ = writeln!(
+            out,
+            "    <kerning first=\"{}\" second=\"{}\" amount=\"{}\"/>",
+            pair.left as u32,
+            pair.right as u32,
+            (pair.advance * font.size).round() as i32
+        );
+    }
+    let _ = writeln!(out, "  </kernings>");
+    let _ = writeln!(out, "</font>");
+    Ok(out)
+}
+
+#[test]
+fn test() {
+    use crate::font::KerningPair;
+
+    let font = ExportFont {
+        name: "test".to_string(),
+        size: 32.0,
+        distance_range: 4.0,
+        line_height: 1.25,
+        ascender: 1.0,
+        descender: -0.25,
+        page_width: 256,
+        page_height: 256,
+        pages: vec!["test_0.png".to_string()],
+        glyphs: vec![
+            ExportGlyph {
+                char: 'A',
+                page: 0,
+                advance: 0.5,
+                plane_bounds: Some([0.0, 0.0, 0.5, 0.75]),
+                atlas_bounds: Some([10.0, 20.0, 26.0, 44.0]),
+            },
+            ExportGlyph::whitespace(' ', 0.25),
+        ],
+        kerning: vec![KerningPair {
+            left: 'A',
+            right: 'V',
+            advance: -0.0625,
+        }],
+    };
+    let text = to_bmfont_text(&font).unwrap();
+    assert!(text.contains(
+        "char id=65 x=10 y=20 width=16 height=24 xoffset=0 yoffset=8 xadvance=16 page=0 chnl=15"
+    ));
+    assert!(text.contains("char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=8"));
+    assert!(text.contains("kerning first=65 second=86 amount=-2"));
+    assert!(to_bmfont_xml(&font)
+        .unwrap()
+        .contains("<char id=\"65\" x=\"10\" y=\"20\""));
+
+    // 每个字符单独缩放，较长边填满32像素：'A'为42.7像素/em，'-'为64像素/em
+    let mut font = font;
+    font.glyphs = vec![
+        ExportGlyph {
+            char: 'A',
+            page: 0,
+            advance: 0.5,
+            plane_bounds: Some([0.0, 0.0, 0.5, 0.75]),
+            atlas_bounds: Some([0.0, 0.0, 21.0, 32.0]),
+        },
+        ExportGlyph {
+            char: '-',
+            page: 0,
+            advance: 0.5,
+            plane_bounds: Some([0.0, 0.3, 0.5, 0.4]),
+            atlas_bounds: Some([32.0, 0.0, 64.0, 5.0]),
+        },
+    ];
+    font.size = 42.67;
+    let err = to_bmfont_text(&font).unwrap_err();
+    assert_eq!(err.char, '-');
+    assert!((err.px_per_em[0] - 64.0).abs() < 1e-3);
+    assert!(to_bmfont_xml(&font).is_err());
+}
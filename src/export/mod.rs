@@ -0,0 +1,93 @@
+//! 图集描述文件的导出
+//!
+//! 把图集页和字符度量写成第三方工具能读取的格式：
+//! * `bmfont` - BMFont/AngelCode的文本和XML格式
+//! * `msdf` - msdf-atlas-gen的json格式
+//! * `texture` - 纹理写为png、ktx2（需要`tex_export` feature）
+//!
+//! 以及`outline` - 圆弧轮廓转回贝塞尔路径和SVG文档，用于检查参与计算的几何
+use serde::{Deserialize, Serialize};
+
+use crate::{font::KerningPair, utils::{TexInfo2, SCALE}};
+
+pub mod bmfont;
+pub mod msdf;
+pub mod outline;
+#[cfg(all(feature = "tex_export", not(target_arch = "wasm32")))]
+pub mod texture;
+
+/// 导出用的字符信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportGlyph {
+    pub char: char,
+    /// 所在页
+    pub page: usize,
+    /// 水平推进量（em）
+    pub advance: f32,
+    /// 平面范围（em，y轴向上）[left, bottom, right, top]，空白字符为None
+    pub plane_bounds: Option<[f32; 4]>,
+    /// 页中的范围（像素，y轴向下）[left, top, right, bottom]
+    pub atlas_bounds: Option<[f32; 4]>,
+}
+
+impl ExportGlyph {
+    /// 从放入图集后的`TexInfo2`创建
+    ///
+    /// # 参数
+    /// * `tex_info` - 字符的纹理信息，`atlas_*`为页坐标
+    /// * `page` - 所在页
+    /// * `advance` - 水平推进量（em），可由`FontFace::horizontal_advance`得到
+    /// * `units_per_em` - 字体的units_per_em，用于把`plane_*`换算为em
+    pub fn new(tex_info: &TexInfo2, page: usize, advance: f32, units_per_em: u16) -> Self {
+        // plane_*为轮廓坐标（每em为SCALE）除以units_per_em
+        let to_em = units_per_em as f32 / SCALE;
+        Self {
+            char: tex_info.char,
+            page,
+            advance,
+            plane_bounds: Some([
+                tex_info.plane_min_x * to_em,
+                tex_info.plane_min_y * to_em,
+                tex_info.plane_max_x * to_em,
+                tex_info.plane_max_y * to_em,
+            ]),
+            atlas_bounds: Some([
+                tex_info.atlas_min_x,
+                tex_info.atlas_min_y,
+                tex_info.atlas_max_x,
+                tex_info.atlas_max_y,
+            ]),
+        }
+    }
+
+    /// 没有纹理的空白字符
+    pub fn whitespace(char: char, advance: f32) -> Self {
+        Self {
+            char,
+            page: 0,
+            advance,
+            plane_bounds: None,
+            atlas_bounds: None,
+        }
+    }
+}
+
+/// 导出用的字体和图集信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportFont {
+    pub name: String,
+    /// 每em对应的像素数，BMFont中的字号
+    pub size: f32,
+    /// 距离场的范围（像素）
+    pub distance_range: f32,
+    /// 行高、上升高度、下降高度（em）
+    pub line_height: f32,
+    pub ascender: f32,
+    pub descender: f32,
+    pub page_width: u32,
+    pub page_height: u32,
+    /// 每页的纹理文件名
+    pub pages: Vec<String>,
+    pub glyphs: Vec<ExportGlyph>,
+    pub kerning: Vec<KerningPair>,
+}
@@ -0,0 +1,131 @@
+//! msdf-atlas-gen的json格式
+//!
+//! 字段与msdf-atlas-gen的`--json`输出一致（`yOrigin`为`top`），
+//! 多页时在每个字符上额外写入`page`，第三方读取时可忽略。
+//!
+//! `size`只写一个值，不检查每个字符的像素/em。按`planeBounds`和`atlasBounds`逐字符映射的读取方式不受影响；
+//! 用`size`和`distanceRange`换算屏幕上距离范围的读取方式，要求图集由`OutlineInfo::compute_sdf_tex_px_per_em`
+//! （烘焙时设置`BakeOptions::px_per_em`）按`size`生成，每个字符单独缩放的图集换算结果会偏大或偏小。
+use serde::Serialize;
+
+use super::ExportFont;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Atlas<'a> {
+    #[serde(rename = "type")]
+    ty: &'a str,
+    distance_range: f32,
+    size: f32,
+    width: u32,
+    height: u32,
+    y_origin: &'a str,
+    pages: &'a [String],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Metrics {
+    em_size: f32,
+    line_height: f32,
+    ascender: f32,
+    descender: f32,
+    underline_y: f32,
+    underline_thickness: f32,
+}
+
+#[derive(Serialize)]
+struct Bounds {
+    left: f32,
+    bottom: f32,
+    right: f32,
+    top: f32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Glyph {
+    unicode: u32,
+    advance: f32,
+    page: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plane_bounds: Option<Bounds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    atlas_bounds: Option<Bounds>,
+}
+
+#[derive(Serialize)]
+struct Kerning {
+    unicode1: u32,
+    unicode2: u32,
+    advance: f32,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    atlas: Atlas<'a>,
+    name: &'a str,
+    metrics: Metrics,
+    glyphs: Vec<Glyph>,
+    kerning: Vec<Kerning>,
+}
+
+/// 导出为msdf-atlas-gen的json格式
+///
+/// # 参数
+/// * `font` - 字体和图集信息
+/// * `ty` - 距离场类型，单通道SDF为`"sdf"`
+pub fn to_msdf_json(font: &ExportFont, ty: &str) -> String {
+    let doc = Document {
+        atlas: Atlas {
+            ty,
+            distance_range: font.distance_range,
+            size: font.size,
+            width: font.page_width,
+            height: font.page_height,
+            y_origin: "top",
+            pages: &font.pages,
+        },
+        name: &font.name,
+        metrics: Metrics {
+            em_size: 1.0,
+            line_height: font.line_height,
+            ascender: font.ascender,
+            descender: font.descender,
+            underline_y: 0.0,
+            underline_thickness: 0.0,
+        },
+        glyphs: font
+            .glyphs
+            .iter()
+            .map(|g| Glyph {
+                unicode: g.char as u32,
+                advance: g.advance,
+                page: g.page,
+                plane_bounds: g.plane_bounds.map(|[left, bottom, right, top]| Bounds {
+                    left,
+                    bottom,
+                    right,
+                    top,
+                }),
+                // 图集中y轴向下，top小于bottom
+                atlas_bounds: g.atlas_bounds.map(|[left, top, right, bottom]| Bounds {
+                    left,
+                    bottom,
+                    right,
+                    top,
+                }),
+            })
+            .collect(),
+        kerning: font
+            .kerning
+            .iter()
+            .map(|k| Kerning {
+                unicode1: k.left as u32,
+                unicode2: k.right as u32,
+                advance: k.advance,
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&doc).unwrap()
+}
@@ -0,0 +1,318 @@
+//! 圆弧轮廓的导出
+//!
+//! 把计算距离场实际使用的圆弧转回三次贝塞尔路径（kurbo的`BezPath`），
+//! 或写成独立的SVG文档，可叠加细分的格子和圆弧端点，用于检查轮廓拟合和细分的结果。
+use std::fmt::Write;
+
+use kurbo::{BezPath, Rect, Shape};
+
+use crate::glyphy::geometry::aabb::Aabb;
+use crate::glyphy::geometry::arc::{arc_midpoint, Arc, ArcEndpoint, ErrorValue};
+use crate::glyphy::util::{GLYPHY_INFINITY, GLYPHY_MAX_D};
+use crate::utils::CellInfo;
+use crate::Point;
+
+/// 导出SVG文档的选项
+#[derive(Debug, Clone)]
+pub struct SvgExportOptions {
+    /// 填充轮廓，否则只描边
+    pub fill: bool,
+    /// 标出圆弧端点，每个轮廓的起点为红色，其余为蓝色
+    pub endpoints: bool,
+    /// 画出细分的格子，只有`CellInfo`有格子
+    pub cells: bool,
+    /// 上下翻转；字体轮廓的y轴向上，导出字符时通常需要翻转
+    pub flip_y: bool,
+}
+
+impl Default for SvgExportOptions {
+    fn default() -> Self {
+        Self {
+            fill: true,
+            endpoints: false,
+            cells: false,
+            flip_y: false,
+        }
+    }
+}
+
+fn to_kurbo(p: Point) -> kurbo::Point {
+    kurbo::Point::new(p.x as f64, p.y as f64)
+}
+
+// 逐段构造路径，回到起点的轮廓自动闭合
+#[derive(Default)]
+struct PathBuilder {
+    path: BezPath,
+    start: Option<Point>,
+    current: Option<Point>,
+    has_segment: bool,
+}
+
+impl PathBuilder {
+    fn move_to(&mut self, p: Point) {
+        self.close();
+        self.path.move_to(to_kurbo(p));
+        self.start = Some(p);
+        self.current = Some(p);
+    }
+
+    fn arc_to(&mut self, p: Point, d: f32) {
+        let current = match self.current {
+            Some(current) => current,
+            None => return self.move_to(p),
+        };
+        if current == p {
+            return;
+        }
+
+        if d == 0.0 {
+            self.path.line_to(to_kurbo(p));
+        } else {
+            push_arc(&mut self.path, current, p, d);
+        }
+        self.current = Some(p);
+        self.has_segment = true;
+    }
+
+    fn close(&mut self) {
+        if self.has_segment && self.start == self.current {
+            self.path.close_path();
+        }
+        self.has_segment = false;
+    }
+
+    fn finish(mut self) -> BezPath {
+        self.close();
+        self.path
+    }
+}
+
+// 圆弧转为三次贝塞尔曲线；Arc::approximate_bezier 的误差随 d 的5次方增长，d 较大时先从中点二分
+fn push_arc(path: &mut BezPath, p0: Point, p1: Point, d: f32) {
+    if d.abs() > GLYPHY_MAX_D {
+        // 从圆弧中点分开，每段的 d = tan(θ/8)
+        let pm = arc_midpoint(p0, p1, d);
+        let half = (d.atan() * 0.5).tan();
+        push_arc(path, p0, pm, half);
+        push_arc(path, pm, p1, half);
+        return;
+    }
+
+    let mut error = ErrorValue { value: 0.0 };
+    let b = Arc::new(p0, p1, d).approximate_bezier(&mut error);
+    path.curve_to(to_kurbo(b.p1), to_kurbo(b.p2), to_kurbo(b.p3));
+}
+
+/// 圆弧端点转为三次贝塞尔路径
+///
+/// `d`为`GLYPHY_INFINITY`的端点开始新的轮廓，回到起点的轮廓会闭合
+pub fn endpoints_to_bez_path(endpoints: &[ArcEndpoint]) -> BezPath {
+    let mut builder = PathBuilder::default();
+    for endpoint in endpoints {
+        let p = Point::new(endpoint.p[0], endpoint.p[1]);
+        if endpoint.d == GLYPHY_INFINITY {
+            builder.move_to(p);
+        } else {
+            builder.arc_to(p, endpoint.d);
+        }
+    }
+    builder.finish()
+}
+
+/// 圆弧转为三次贝塞尔路径，圆弧的起点不是上一段的终点时开始新的轮廓
+pub fn arcs_to_bez_path(arcs: &[Arc]) -> BezPath {
+    let mut builder = PathBuilder::default();
+    for arc in arcs {
+        if builder.current != Some(arc.p0) {
+            builder.move_to(arc.p0);
+        }
+        builder.arc_to(arc.p1, arc.d);
+    }
+    builder.finish()
+}
+
+/// 圆弧端点写成SVG文档
+///
+/// # 参数
+/// * `endpoints` - 圆弧端点
+/// * `options` - 导出选项，端点没有格子，忽略`cells`
+pub fn endpoints_to_svg(endpoints: &[ArcEndpoint], options: &SvgExportOptions) -> String {
+    let path = endpoints_to_bez_path(endpoints);
+    let points: Vec<(Point, bool)> = if options.endpoints {
+        endpoints
+            .iter()
+            .map(|e| (Point::new(e.p[0], e.p[1]), e.d == GLYPHY_INFINITY))
+            .collect()
+    } else {
+        vec![]
+    };
+    svg_document(&path, &points, &[], options)
+}
+
+/// 细分结果写成SVG文档，格子为细分得到的格子
+pub fn cell_info_to_svg(cell: &CellInfo, options: &SvgExportOptions) -> String {
+    let path = arcs_to_bez_path(&cell.arcs);
+
+    let mut points = vec![];
+    if options.endpoints {
+        let mut current = None;
+        for arc in &cell.arcs {
+            if current != Some(arc.p0) {
+                points.push((arc.p0, true));
+            }
+            points.push((arc.p1, false));
+            current = Some(arc.p1);
+        }
+    }
+
+    let cells: Vec<Aabb> = if options.cells {
+        cell.info.iter().map(|(_, aabb)| *aabb).collect()
+    } else {
+        vec![]
+    };
+    svg_document(&path, &points, &cells, options)
+}
+
+/// 路径写成SVG文档，视口为路径和格子的包围盒外扩5%
+///
+/// # 参数
+/// * `path` - 轮廓
+/// * `endpoints` - 要标出的端点，`true`为轮廓的起点
+/// * `cells` - 要画出的格子
+/// * `options` - 导出选项，只使用`fill`和`flip_y`
+pub fn svg_document(
+    path: &BezPath,
+    endpoints: &[(Point, bool)],
+    cells: &[Aabb],
+    options: &SvgExportOptions,
+) -> String {
+    let mut bounds: Option<Rect> = if path.elements().is_empty() {
+        None
+    } else {
+        Some(path.bounding_box())
+    };
+    for cell in cells {
+        let rect = Rect::new(
+            cell.mins.x as f64,
+            cell.mins.y as f64,
+            cell.maxs.x as f64,
+            cell.maxs.y as f64,
+        );
+        bounds = Some(bounds.map_or(rect, |bounds| bounds.union(rect)));
+    }
+    let bounds = bounds.unwrap_or(Rect::ZERO);
+    let margin = (bounds.width().max(bounds.height()) * 0.05).max(1.0);
+    let view = bounds.inflate(margin, margin);
+    // 线宽和端点半径随视口大小变化
+    let unit = view.width().max(view.height()) / 500.0;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        view.x0,
+        view.y0,
+        view.width(),
+        view.height()
+    );
+    if options.flip_y {
+        // 以包围盒的水平中线翻转，视口不变
+        let _ = writeln!(
+            out,
+            r#"<g transform="matrix(1 0 0 -1 0 {})">"#,
+            view.y0 + view.y1
+        );
+    } else {
+        let _ = writeln!(out, "<g>");
+    }
+
+    for cell in cells {
+        let _ = writeln!(
+            out,
+            r##"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="#999999" stroke-width="{}"/>"##,
+            cell.mins.x,
+            cell.mins.y,
+            cell.maxs.x - cell.mins.x,
+            cell.maxs.y - cell.mins.y,
+            unit
+        );
+    }
+
+    let (fill, stroke) = if options.fill {
+        ("#000000", "none")
+    } else {
+        ("none", "#000000")
+    };
+    let _ = writeln!(
+        out,
+        r#"<path d="{}" fill="{}" fill-rule="nonzero" stroke="{}" stroke-width="{}"/>"#,
+        path.to_svg(),
+        fill,
+        stroke,
+        unit * 2.0
+    );
+
+    for (p, is_start) in endpoints {
+        let _ = writeln!(
+            out,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            p.x,
+            p.y,
+            unit * 3.0,
+            if *is_start { "#ff0000" } else { "#0000ff" }
+        );
+    }
+
+    let _ = writeln!(out, "</g>");
+    let _ = writeln!(out, "</svg>");
+    out
+}
+
+#[test]
+fn test() {
+    use crate::shape::Circle;
+    use kurbo::PathEl;
+
+    let info = Circle::new(50.0, 50.0, 40.0).unwrap().get_svg_info();
+    let path = endpoints_to_bez_path(&info.arc_endpoints);
+    let elements = path.elements();
+    assert!(matches!(elements[0], PathEl::MoveTo(_)));
+    assert!(matches!(elements.last(), Some(PathEl::ClosePath)));
+    for el in elements {
+        if let PathEl::CurveTo(_, _, p) = el {
+            assert!(((*p - kurbo::Point::new(50.0, 50.0)).hypot() - 40.0).abs() < 1e-3);
+        }
+    }
+
+    // 半圆的 d = 1，分成两段
+    let endpoints = vec![
+        ArcEndpoint::new(0.0, 0.0, GLYPHY_INFINITY),
+        ArcEndpoint::new(2.0, 0.0, 1.0),
+    ];
+    let path = endpoints_to_bez_path(&endpoints);
+    let elements = path.elements();
+    assert_eq!(elements.len(), 3);
+    match elements[1] {
+        PathEl::CurveTo(_, _, p) => {
+            assert!((p.x - 1.0).abs() < 1e-5 && (p.y + 1.0).abs() < 1e-5);
+        }
+        _ => panic!("expected a curve"),
+    }
+
+    let options = SvgExportOptions {
+        endpoints: true,
+        ..Default::default()
+    };
+    let svg = endpoints_to_svg(&info.arc_endpoints, &options);
+    assert!(svg.starts_with("<svg"));
+    assert_eq!(svg.matches("<circle").count(), info.arc_endpoints.len());
+
+    let cell = info.compute_near_arcs(1.0);
+    let options = SvgExportOptions {
+        cells: true,
+        ..Default::default()
+    };
+    let svg = cell_info_to_svg(&cell, &options);
+    assert_eq!(svg.matches("<rect").count(), cell.info.len());
+}
@@ -0,0 +1,406 @@
+//! 纹理导出：png和ktx2
+//!
+//! 各纹理的通道布局：
+//! * `SdfInfo2.sdf_tex`、`BlurInfo.tex` - 单通道R8，第0行为顶部（字符在`encode_sdf`中已翻转y轴，svg本身y轴向下）
+//! * `SdfInfo.index_tex` - 双通道RG8（宽索引为RGBA8），`grid_w * grid_h`，第0行对应`extents`的最小y
+//! * `SdfInfo.data_tex` - 四通道RGBA8（`DataFormat::Rgba16`时为每通道小端u16的RGBA16），单独编码时为一行
+//!
+//! png按图片方向写出（y轴向上的纹理会翻转），双通道写为灰度+alpha；
+//! ktx2保持纹理数据不变，把方向写入`KTXorientation`，可直接上传给GPU。
+use std::{fs, io, path::Path};
+
+use image::ColorType;
+
+use crate::{
+    blur::BlurInfo,
+    glyphy::blob::{DataFormat, IndexFormat, SdfInfo},
+    mipmap::SdfMipLevel,
+    utils::SdfInfo2,
+};
+
+/// 纹理的像素格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TexFormat {
+    R8,
+    Rg8,
+    Rgba8,
+    /// 每通道小端u16
+    Rgba16,
+}
+
+impl TexFormat {
+    /// 通道数
+    pub fn channels(&self) -> usize {
+        match self {
+            TexFormat::R8 => 1,
+            TexFormat::Rg8 => 2,
+            TexFormat::Rgba8 | TexFormat::Rgba16 => 4,
+        }
+    }
+
+    /// 每通道字节数
+    pub fn channel_bytes(&self) -> usize {
+        match self {
+            TexFormat::Rgba16 => 2,
+            _ => 1,
+        }
+    }
+
+    /// 每像素字节数
+    pub fn bytes(&self) -> usize {
+        self.channels() * self.channel_bytes()
+    }
+
+    /// 对应的VkFormat（UNORM）
+    fn vk_format(&self) -> u32 {
+        match self {
+            TexFormat::R8 => 9,
+            TexFormat::Rg8 => 16,
+            TexFormat::Rgba8 => 37,
+            TexFormat::Rgba16 => 91,
+        }
+    }
+
+    fn color_type(&self) -> ColorType {
+        match self {
+            TexFormat::R8 => ColorType::L8,
+            TexFormat::Rg8 => ColorType::La8,
+            TexFormat::Rgba8 => ColorType::Rgba8,
+            TexFormat::Rgba16 => ColorType::Rgba16,
+        }
+    }
+}
+
+/// 待导出的一张纹理
+#[derive(Debug, Clone, Copy)]
+pub struct TexView<'a> {
+    pub data: &'a [u8],
+    pub width: u32,
+    pub height: u32,
+    pub format: TexFormat,
+    /// 第0行是否为底部
+    pub y_up: bool,
+}
+
+impl<'a> TexView<'a> {
+    pub fn from_sdf_info2(info: &'a SdfInfo2) -> Self {
+        Self {
+            data: &info.sdf_tex,
+            width: info.tex_width,
+            height: info.tex_height,
+            format: TexFormat::R8,
+            y_up: false,
+        }
+    }
+
+    pub fn from_blur(info: &'a BlurInfo) -> Self {
+        Self {
+            data: &info.tex,
+            width: info.width as u32,
+            height: info.height as u32,
+            format: TexFormat::R8,
+            y_up: false,
+        }
+    }
+
+    /// glyphy的索引纹理
+    ///
+    /// # 参数
+    /// * `y_up` - 轮廓坐标是否y轴向上，字体为true，svg为false
+    pub fn index_tex(info: &'a SdfInfo, y_up: bool) -> Self {
+        Self {
+            data: &info.index_tex,
+            width: info.grid_size[0] as u32,
+            height: info.grid_size[1] as u32,
+            format: match info.tex_info.index_format {
+                IndexFormat::U16 => TexFormat::Rg8,
+                IndexFormat::Wide => TexFormat::Rgba8,
+            },
+            y_up,
+        }
+    }
+
+    /// glyphy的数据纹理，与示例中上传的布局一致：宽为像素数，高为1
+    pub fn data_tex(info: &'a SdfInfo) -> Self {
+        let data_format = info.tex_info.data_format;
+        Self {
+            data: &info.data_tex,
+            width: (info.data_tex.len() / data_format.bytes()) as u32,
+            height: 1,
+            format: match data_format {
+                DataFormat::Rgba8 => TexFormat::Rgba8,
+                DataFormat::Rgba16 => TexFormat::Rgba16,
+            },
+            y_up: false,
+        }
+    }
+
+    fn check(&self) {
+        assert_eq!(
+            self.data.len(),
+            self.width as usize * self.height as usize * self.format.bytes()
+        );
+    }
+}
+
+/// 写出png，y轴向上的纹理会翻转为图片方向
+pub fn write_png(path: impl AsRef<Path>, tex: &TexView) -> io::Result<()> {
+    tex.check();
+    let mut data = if tex.y_up {
+        let row = tex.width as usize * tex.format.bytes();
+        tex.data.chunks(row).rev().flatten().copied().collect::<Vec<u8>>()
+    } else {
+        tex.data.to_vec()
+    };
+    // image的16位数据为本机字节序
+    if tex.format.channel_bytes() == 2 {
+        for c in data.chunks_mut(2) {
+            let v = u16::from_le_bytes([c[0], c[1]]);
+            c.copy_from_slice(&v.to_ne_bytes());
+        }
+    }
+    image::save_buffer(path, &data, tex.width, tex.height, tex.format.color_type())
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
+
+/// 编码为ktx2容器（无超压缩）
+///
+/// # 参数
+/// * `format` - 像素格式
+/// * `y_up` - 第0行是否为底部
+/// * `levels` - 各级mipmap，第一个为最大的一级
+pub fn encode_ktx2(format: TexFormat, y_up: bool, levels: &[SdfMipLevel]) -> Vec<u8> {
+    assert!(!levels.is_empty());
+    let channels = format.channels();
+    let channel_bits = format.channel_bytes() as u32 * 8;
+    for level in levels {
+        assert_eq!(
+            level.data.len(),
+            level.width as usize * level.height as usize * format.bytes()
+        );
+    }
+
+    // 数据格式描述（Khronos Basic DFD），每个通道一个8位或16位UNORM样本
+    let mut dfd = vec![];
+    let block_size = 24 + 16 * channels as u32;
+    push_u32(&mut dfd, 4 + block_size);
+    push_u32(&mut dfd, 0); // vendorId = 0, descriptorType = 0
+    push_u32(&mut dfd, 2 | (block_size << 16)); // versionNumber = 2
+    // colorModel = RGBSDA, colorPrimaries = BT709, transferFunction = LINEAR, flags = 0
+    dfd.extend_from_slice(&[1, 1, 1, 0]);
+    push_u32(&mut dfd, 0); // texelBlockDimension
+    dfd.extend_from_slice(&[format.bytes() as u8, 0, 0, 0]); // bytesPlane0..3
+    push_u32(&mut dfd, 0); // bytesPlane4..7
+    let channel_ids: &[u32] = match format {
+        TexFormat::R8 => &[0],
+        TexFormat::Rg8 => &[0, 1],
+        TexFormat::Rgba8 | TexFormat::Rgba16 => &[0, 1, 2, 15],
+    };
+    for (i, id) in channel_ids.iter().enumerate() {
+        // bitOffset | bitLength - 1 | channelType
+        push_u32(
+            &mut dfd,
+            (i as u32 * channel_bits) | ((channel_bits - 1) << 16) | (id << 24),
+        );
+        push_u32(&mut dfd, 0); // samplePosition
+        push_u32(&mut dfd, 0); // sampleLower
+        push_u32(&mut dfd, (1 << channel_bits) - 1); // sampleUpper
+    }
+
+    let mut kvd = vec![];
+    push_kv(&mut kvd, "KTXorientation", if y_up { "ru" } else { "rd" });
+    push_kv(&mut kvd, "KTXwriter", "pi_sdf");
+
+    let level_index_len = 24 * levels.len();
+    let dfd_offset = 80 + level_index_len;
+    let kvd_offset = dfd_offset + dfd.len();
+    // 每级数据的起始位置按 lcm(像素字节数, 4) 对齐
+    let level_align = lcm(format.bytes(), 4);
+    let mut data_offset = kvd_offset + kvd.len();
+
+    // 数据按从小到大排列，索引按从大到小排列
+    let mut level_offsets = vec![0; levels.len()];
+    for (i, level) in levels.iter().enumerate().rev() {
+        level_offsets[i] = align(data_offset, level_align);
+        data_offset = level_offsets[i] + level.data.len();
+    }
+
+    let mut out = Vec::with_capacity(data_offset);
+    out.extend_from_slice(&[
+        0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+    ]);
+    push_u32(&mut out, format.vk_format());
+    push_u32(&mut out, format.channel_bytes() as u32); // typeSize
+    push_u32(&mut out, levels[0].width);
+    push_u32(&mut out, levels[0].height);
+    push_u32(&mut out, 0); // pixelDepth
+    push_u32(&mut out, 0); // layerCount
+    push_u32(&mut out, 1); // faceCount
+    push_u32(&mut out, levels.len() as u32);
+    push_u32(&mut out, 0); // supercompressionScheme
+
+    push_u32(&mut out, dfd_offset as u32);
+    push_u32(&mut out, dfd.len() as u32);
+    push_u32(&mut out, kvd_offset as u32);
+    push_u32(&mut out, kvd.len() as u32);
+    push_u64(&mut out, 0); // sgdByteOffset
+    push_u64(&mut out, 0); // sgdByteLength
+
+    for (level, offset) in levels.iter().zip(&level_offsets) {
+        push_u64(&mut out, *offset as u64);
+        push_u64(&mut out, level.data.len() as u64);
+        push_u64(&mut out, level.data.len() as u64);
+    }
+    out.extend_from_slice(&dfd);
+    out.extend_from_slice(&kvd);
+
+    for (i, level) in levels.iter().enumerate().rev() {
+        out.resize(level_offsets[i], 0);
+        out.extend_from_slice(&level.data);
+    }
+    out
+}
+
+/// 写出单级的ktx2
+pub fn write_ktx2(path: impl AsRef<Path>, tex: &TexView) -> io::Result<()> {
+    tex.check();
+    let level = SdfMipLevel {
+        width: tex.width,
+        height: tex.height,
+        data: tex.data.to_vec(),
+    };
+    fs::write(path, encode_ktx2(tex.format, tex.y_up, &[level]))
+}
+
+impl SdfInfo2 {
+    /// 把SDF纹理写为png
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        write_png(path, &TexView::from_sdf_info2(self))
+    }
+
+    /// 把SDF纹理连同mipmap（见`generate_mips`）写为ktx2
+    pub fn write_ktx2(&self, path: impl AsRef<Path>, max_levels: usize) -> io::Result<()> {
+        fs::write(
+            path,
+            encode_ktx2(TexFormat::R8, false, &self.generate_mips(max_levels)),
+        )
+    }
+}
+
+fn push_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn push_u64(out: &mut Vec<u8>, v: u64) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn push_kv(out: &mut Vec<u8>, key: &str, value: &str) {
+    let len = key.len() + 1 + value.len() + 1;
+    push_u32(out, len as u32);
+    out.extend_from_slice(key.as_bytes());
+    out.push(0);
+    out.extend_from_slice(value.as_bytes());
+    out.push(0);
+    out.resize(align(out.len(), 4), 0);
+}
+
+fn align(v: usize, a: usize) -> usize {
+    v.div_ceil(a) * a
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+#[test]
+fn test() {
+    let levels = vec![
+        SdfMipLevel {
+            width: 4,
+            height: 2,
+            data: vec![1; 8],
+        },
+        SdfMipLevel {
+            width: 2,
+            height: 1,
+            data: vec![2; 2],
+        },
+    ];
+    let ktx = encode_ktx2(TexFormat::R8, false, &levels);
+    let read_u32 = |o: usize| u32::from_le_bytes(ktx[o..o + 4].try_into().unwrap());
+    let read_u64 = |o: usize| u64::from_le_bytes(ktx[o..o + 8].try_into().unwrap()) as usize;
+    assert_eq!(&ktx[1..4], b"KTX");
+    assert_eq!((read_u32(12), read_u32(20), read_u32(24), read_u32(40)), (9, 4, 2, 2));
+    // 第0级在文件末尾，第1级在其之前
+    let (offset0, len0) = (read_u64(80), read_u64(88));
+    let (offset1, len1) = (read_u64(104), read_u64(112));
+    assert_eq!((len0, len1), (8, 2));
+    assert!(offset1 < offset0);
+    assert_eq!(offset0 % 4, 0);
+    assert_eq!(&ktx[offset0..offset0 + 8], &[1; 8]);
+    assert_eq!(&ktx[offset1..offset1 + 2], &[2; 2]);
+    // dfd紧跟在级别索引之后
+    assert_eq!(read_u32(48), 80 + 24 * 2);
+}
+
+#[test]
+fn test_rgba16() {
+    use crate::shape::Circle;
+
+    let cell = Circle::new(50.0, 50.0, 40.0)
+        .unwrap()
+        .get_svg_info()
+        .compute_near_arcs(1.0);
+    let info = cell
+        .encode_blob_arc_with_format(DataFormat::Rgba16)
+        .try_encode_tex()
+        .unwrap();
+    let tex = TexView::data_tex(&info);
+    assert_eq!(tex.format, TexFormat::Rgba16);
+    assert_eq!(tex.width as usize * 8, info.data_tex.len());
+
+    let expected: Vec<u16> = info
+        .data_tex
+        .chunks(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let path = std::env::temp_dir().join("pi_sdf_test_rgba16.png");
+    write_png(&path, &tex).unwrap();
+    let image = image::open(&path).unwrap().into_rgba16();
+    let _ = fs::remove_file(&path);
+    assert_eq!(image.dimensions(), (tex.width, 1));
+    assert_eq!(image.into_raw(), expected);
+
+    let half = tex.width / 2;
+    let ktx = encode_ktx2(
+        tex.format,
+        false,
+        &[
+            SdfMipLevel {
+                width: tex.width,
+                height: 1,
+                data: info.data_tex.clone(),
+            },
+            SdfMipLevel {
+                width: half,
+                height: 1,
+                data: info.data_tex[..half as usize * 8].to_vec(),
+            },
+        ],
+    );
+    let read_u32 = |o: usize| u32::from_le_bytes(ktx[o..o + 4].try_into().unwrap());
+    let read_u64 = |o: usize| u64::from_le_bytes(ktx[o..o + 8].try_into().unwrap()) as usize;
+    assert_eq!((read_u32(12), read_u32(16), read_u32(20)), (91, 2, tex.width));
+    let (offset, len) = (read_u64(80), read_u64(88));
+    assert_eq!(&ktx[offset..offset + len], &info.data_tex[..]);
+    // 每级的起始位置按 lcm(8, 4) = 8 对齐
+    let offset1 = read_u64(104);
+    assert_eq!((offset % 8, offset1 % 8), (0, 0));
+    assert!(offset1 + half as usize * 8 <= offset);
+    assert_eq!(ktx.len(), offset + len);
+}
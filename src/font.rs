@@ -1,9 +1,9 @@
 use crate::{
     glyphy::{geometry::segment::{PPoint, PSegment}, util::GLYPHY_EPSILON},
-    utils::{compute_cell_range, CellInfo, CHARS},
+    utils::{compute_cell_range, compute_cell_range_rect, CellInfo, CHARS},
 };
 use allsorts::{
-    binary::read::ReadScope, font::MatchingPresentation, font_data::{DynamicFontTableProvider, FontData}, gsub::{FeatureMask, Features}, outline::OutlineBuilder, tables::{glyf::GlyfTable, loca::LocaTable, FontTableProvider, HeadTable}, tag, Font
+    binary::read::ReadScope, font::MatchingPresentation, font_data::{DynamicFontTableProvider, FontData}, gsub::{FeatureMask, Features}, layout::PosLookup, outline::OutlineBuilder, tables::{glyf::GlyfTable, loca::LocaTable, FontTableProvider, HeadTable}, tag, Font
 };
 use pi_share::Share;
 #[cfg(not(target_arch = "wasm32"))]
@@ -12,7 +12,7 @@ use ttf_parser::GlyphId;
 use crate::system_font::{FontLoader, SystemFont};
 use crate::{
     glyphy::{
-        blob::recursion_near_arcs_of_cell,
+        blob::{recursion_near_arcs_of_cell, CellBudget, CellBudgetError},
         geometry::{
             aabb::{Aabb, Direction},
             arc::{Arc, ArcEndpoint},
@@ -24,7 +24,8 @@ use crate::{
     utils::{GlyphVisitor, OutlineInfo, SCALE, TOLERANCE},
     Point,
 };
-use std::{char, sync::RwLock};
+use serde::{Deserialize, Serialize};
+use std::{char, collections::HashMap, sync::RwLock};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
@@ -256,6 +257,56 @@ impl FontFace {
         endpoints: &Vec<ArcEndpoint>,
     ) -> CellInfo {
         let extents = compute_cell_range(extents, scale);
+        Self::compute_near_arcs_of_range(extents, endpoints, &CellBudget::unlimited()).0
+    }
+
+    /// 计算近段弧的信息，格子范围不补齐成正方形，用于非方形纹理。
+    ///
+    /// # 参数
+    /// * `extents: Aabb` - 当前细胞的活动范围。
+    /// * `scale: f32` - 缩放比例。
+    /// * `endpoints: &Vec<ArcEndpoint>` - 圆弧端点的集合。
+    ///
+    /// # 返回值
+    /// * `CellInfo` - 包含包围盒、近段弧、最小宽度和高度等信息。
+    pub fn compute_near_arcs_rect(
+        extents: Aabb,
+        scale: f32,
+        endpoints: &Vec<ArcEndpoint>,
+    ) -> CellInfo {
+        let extents = compute_cell_range_rect(extents, scale);
+        Self::compute_near_arcs_of_range(extents, endpoints, &CellBudget::unlimited()).0
+    }
+
+    /// 计算近段弧的信息，并保证每个格子的圆弧数不超过预算。
+    ///
+    /// 格子的圆弧数超出预算时会继续细分，直到满足预算或到达预算允许的最小格子。
+    ///
+    /// # 参数
+    /// * `extents: Aabb` - 当前细胞的活动范围。
+    /// * `scale: f32` - 缩放比例。
+    /// * `endpoints: &Vec<ArcEndpoint>` - 圆弧端点的集合。
+    /// * `budget: &CellBudget` - 每个格子的圆弧预算。
+    ///
+    /// # 返回值
+    /// * `Result<CellInfo, CellBudgetError>` - 到达最小格子仍超出预算时，返回超出预算的格子。
+    pub fn compute_near_arcs_with_budget(
+        extents: Aabb,
+        scale: f32,
+        endpoints: &Vec<ArcEndpoint>,
+        budget: &CellBudget,
+    ) -> Result<CellInfo, CellBudgetError> {
+        let extents = compute_cell_range(extents, scale);
+        let (info, overflows) = Self::compute_near_arcs_of_range(extents, endpoints, budget);
+        CellBudgetError::check(budget, overflows)?;
+        Ok(info)
+    }
+
+    fn compute_near_arcs_of_range(
+        extents: Aabb,
+        endpoints: &Vec<ArcEndpoint>,
+        budget: &CellBudget,
+    ) -> (CellInfo, Vec<(usize, Aabb)>) {
         log::debug!("extents: {:?}", extents);
 
         if endpoints.len() > 0 {
@@ -292,6 +343,7 @@ impl FontFace {
         let mut result_arcs = vec![];
         let mut temp = Vec::with_capacity(arcs.len());
         let mut tempidx = vec![];
+        let mut overflows = vec![];
         let (ab1, ab2) = extents.half(Direction::Col);
         // 二分法递归细分格子，直到格子周围的圆弧数量少于一定数目或达到停止条件。
         recursion_near_arcs_of_cell(
@@ -309,7 +361,9 @@ impl FontFace {
             &mut temp,
             &mut tempsegment,
             // startid,
-            &mut tempidx
+            &mut tempidx,
+            budget,
+            &mut overflows,
         );
         recursion_near_arcs_of_cell(
             // &near_arcs,
@@ -326,19 +380,170 @@ impl FontFace {
             &mut temp,
             &mut tempsegment,
             // startid,
-            &mut tempidx
+            &mut tempidx,
+            budget,
+            &mut overflows,
         );
-        CellInfo {
-            extents,
-            arcs: near_arcs,
-            info: result_arcs,
-            min_width,
-            min_height,
-            is_area: true,
+        (
+            CellInfo {
+                extents,
+                arcs: near_arcs,
+                info: result_arcs,
+                min_width,
+                min_height,
+                is_area: true,
+            },
+            overflows,
+        )
+    }
+
+    /// 查询文字中两两字符之间的字偶距
+    ///
+    /// 读取GPOS中`kern`特性的成对调整（PairPos）；字体的GPOS没有字偶距时，读取旧式`kern`表（format 0）。
+    ///
+    /// # 参数
+    /// - `text`: 需要查询的字符，重复的字符只计算一次
+    /// # 返回值
+    /// 非零的字偶距，单位为em
+    pub fn kerning_pairs(&mut self, text: &str) -> Vec<KerningPair> {
+        let mut chars: Vec<char> = text.chars().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        let glyphs: Vec<u16> = chars
+            .iter()
+            .map(|ch| {
+                self.font
+                    .lookup_glyph_index(*ch, MatchingPresentation::NotRequired, None)
+                    .0
+            })
+            .collect();
+
+        let mut table = self.gpos_kerning(&glyphs);
+        if table.is_empty() {
+            if let Ok(Some(data)) = self.font.font_table_provider.table_data(tag::KERN) {
+                table = parse_kern_table(&data);
+            }
+        }
+        if table.is_empty() {
+            return vec![];
         }
+
+        let mut pairs = vec![];
+        for (i, left) in chars.iter().enumerate() {
+            for (j, right) in chars.iter().enumerate() {
+                if let Some(value) = table.get(&(glyphs[i], glyphs[j])) {
+                    pairs.push(KerningPair {
+                        left: *left,
+                        right: *right,
+                        advance: *value as f32 / self.units_per_em as f32,
+                    });
+                }
+            }
+        }
+        pairs
+    }
+
+    // GPOS中`kern`特性的成对调整，返回glyphs两两之间(左字形, 右字形) -> 调整量（字体单位）
+    fn gpos_kerning(&mut self, glyphs: &[u16]) -> HashMap<(u16, u16), i16> {
+        let mut table = HashMap::new();
+        let Ok(Some(cache)) = self.font.gpos_cache() else {
+            return table;
+        };
+        let layout = &cache.layout_table;
+        let (Some(features), Some(lookup_list)) =
+            (&layout.opt_feature_list, &layout.opt_lookup_list)
+        else {
+            return table;
+        };
+
+        let mut indices = vec![];
+        let mut i = 0;
+        while let Ok(record) = features.nth_feature_record(i) {
+            if record.feature_tag == tag::KERN {
+                indices.extend(record.feature_table().lookup_indices.iter().map(|i| *i as usize));
+            }
+            i += 1;
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        let lookups: Vec<_> = indices
+            .into_iter()
+            .filter_map(|i| lookup_list.lookup_cache_gpos(&cache, i).ok())
+            .collect();
+        if lookups.is_empty() {
+            return table;
+        }
+
+        for &left in glyphs {
+            for &right in glyphs {
+                let mut value = 0;
+                for lookup in &lookups {
+                    let PosLookup::PairPos(subtables) = &lookup.lookup_subtables else {
+                        continue;
+                    };
+                    // 一个查找表中只有第一个匹配的子表生效；只取左字形的推进量调整
+                    for subtable in subtables {
+                        if let Ok(Some((adjust, _))) = subtable.apply(left, right) {
+                            value += adjust.map_or(0, |a| a.x_advance as i32);
+                            break;
+                        }
+                    }
+                }
+                if value != 0 {
+                    table.insert((left, right), value as i16);
+                }
+            }
+        }
+        table
     }
 }
 
+/// 两个字符之间的字偶距
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KerningPair {
+    pub left: char,
+    pub right: char,
+    /// 调整量，单位为em
+    pub advance: f32,
+}
+
+/// 解析`kern`表中水平方向的format 0子表，返回(左字形, 右字形) -> 调整量（字体单位）
+fn parse_kern_table(data: &[u8]) -> HashMap<(u16, u16), i16> {
+    let read_u16 = |offset: usize| -> Option<u16> {
+        data.get(offset..offset + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+
+    let mut table = HashMap::new();
+    // 只支持OpenType格式的表头：version(u16) nTables(u16)
+    let (Some(0), Some(count)) = (read_u16(0), read_u16(2)) else {
+        return table;
+    };
+    let mut offset = 4;
+    for _ in 0..count {
+        let (Some(length), Some(coverage)) = (read_u16(offset + 2), read_u16(offset + 4)) else {
+            break;
+        };
+        // format 0，水平方向，非最小值，非垂直于行
+        if coverage >> 8 == 0 && coverage & 0b111 == 0b001 {
+            let n_pairs = read_u16(offset + 6).unwrap_or(0) as usize;
+            let pairs = offset + 14;
+            for i in 0..n_pairs {
+                let p = pairs + i * 6;
+                match (read_u16(p), read_u16(p + 2), read_u16(p + 4)) {
+                    (Some(left), Some(right), Some(value)) if value != 0 => {
+                        table.insert((left, right), value as i16);
+                    }
+                    (Some(_), Some(_), Some(_)) => {}
+                    _ => break,
+                }
+            }
+        }
+        offset += length as usize;
+    }
+    table
+}
+
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl FontFace {
     #[cfg(not(target_arch = "wasm32"))]
@@ -457,6 +662,14 @@ impl FontFace {
         glyph_index
     }
 
+    /// 通过 wasm 绑定查询字偶距
+    ///
+    /// # 返回值
+    /// 序列化后的`Vec<KerningPair>`
+    pub fn kerning_pairs_of_wasm(&mut self, text: &str) -> Vec<u8> {
+        bitcode::serialize(&self.kerning_pairs(text)).unwrap()
+    }
+
     pub fn glyph_indexs(&mut self, text: &str, script: u32) -> Vec<u32> {
         // let g = text.split_word_bounds().collect::<Vec<&str>>();
         // let mut glyphs = Vec::new();
@@ -541,6 +754,17 @@ impl FontFace {
     /// # 返回值
     /// 轮廓信息（OutlineInfo）
     pub fn to_outline(&mut self, ch: char) -> OutlineInfo {
+        self.to_outline_with_tolerance(ch, TOLERANCE)
+    }
+
+    /// 按指定的拟合精度将字符转换为轮廓信息。
+    ///
+    /// # 参数
+    /// - `ch`: 查询的字符
+    /// - `tolerance`: 圆弧拟合贝塞尔曲线的精度，按 units_per_em 缩放，默认为`TOLERANCE`
+    /// # 返回值
+    /// 轮廓信息（OutlineInfo），`max_error`为达到的最大误差，`fit_failed`表示有曲线超出精度
+    pub fn to_outline_with_tolerance(&mut self, ch: char, tolerance: f32) -> OutlineInfo {
         let mut glyph_index =
             self.font
                 .lookup_glyph_index(ch, MatchingPresentation::NotRequired, None).0 as u32;
@@ -548,7 +772,7 @@ impl FontFace {
         if glyph_index == 0 {
             glyph_index = default_glyph_index(ch);
         }
-        let mut o = self.to_outline_of_glyph_index(glyph_index);
+        let mut o = self.to_outline_of_glyph_index_with_tolerance(glyph_index, tolerance);
         o.char = ch;
         o
     }
@@ -560,6 +784,21 @@ impl FontFace {
     /// # 返回值
     /// 轮廓信息（OutlineInfo）
     pub fn to_outline_of_glyph_index(&mut self, glyph_index: u32) -> OutlineInfo {
+        self.to_outline_of_glyph_index_with_tolerance(glyph_index, TOLERANCE)
+    }
+
+    /// 按指定的拟合精度将字形转换为轮廓信息。
+    ///
+    /// # 参数
+    /// - `glyph_index`: 字形索引
+    /// - `tolerance`: 圆弧拟合贝塞尔曲线的精度，按 units_per_em 缩放，默认为`TOLERANCE`
+    /// # 返回值
+    /// 轮廓信息（OutlineInfo）
+    pub fn to_outline_of_glyph_index_with_tolerance(
+        &mut self,
+        glyph_index: u32,
+        tolerance: f32,
+    ) -> OutlineInfo {
         let mut bbox2 = Aabb::new(Point::new(0.0, 0.0), Point::new(0.0, 0.0));
         let mut sink = GlyphVisitor::new(1.0);
         assert_ne!(glyph_index, 0);
@@ -571,7 +810,7 @@ impl FontFace {
             let mut lock = DEFAULT_FONT.write().unwrap();
 
             let units_per_em = lock.as_ref().unwrap().face.units_per_em() ;
-            sink.accumulate.tolerance = units_per_em as f32 * TOLERANCE;
+            sink.accumulate.set_tolerance(units_per_em as f32 * tolerance);
             sink.scale = SCALE / units_per_em as f32;
             lock.as_mut().unwrap().face.outline_glyph(glyph_index, &mut sink);
 
@@ -588,7 +827,7 @@ impl FontFace {
        
        if glyph_index <= DEFAULT_GAP {
             let units_per_em = self.units_per_em;
-            sink.accumulate.tolerance = units_per_em as f32 * TOLERANCE;
+            sink.accumulate.set_tolerance(units_per_em as f32 * tolerance);
             sink.scale = SCALE / units_per_em as f32;
 
             advance = self.font.horizontal_advance(glyph_index as u16).unwrap() / units_per_em;
@@ -610,7 +849,7 @@ impl FontFace {
         // assert!(area.abs() > GLYPHY_EPSILON);
 
         let GlyphVisitor {
-            accumulate: GlyphyArcAccumulator { result, .. },
+            accumulate: GlyphyArcAccumulator { result, max_error, success, .. },
             bbox,
             // #[cfg(feature = "debug")]
             svg_paths,
@@ -626,7 +865,9 @@ impl FontFace {
             extents: vec![bbox.mins.x, bbox.mins.y, bbox.maxs.x, bbox.maxs.y],
             // #[cfg(feature = "debug")]
             svg_paths,
-            is_cw
+            is_cw,
+            max_error,
+            fit_failed: !success,
         }
     }
 
@@ -638,15 +879,7 @@ impl FontFace {
     /// 轮廓信息（WasmOutlineInfo）
     pub fn to_outline_of_wasm(&mut self, ch: char) -> WasmOutlineInfo {
         let outline = self.to_outline(ch);
-        let buf = bitcode::serialize(&outline).unwrap();
-        WasmOutlineInfo {
-            buf,
-            units_per_em: outline.units_per_em,
-            advance: outline.advance,
-            bbox: outline.bbox,
-            extents: outline.extents,
-            is_cw: outline.is_cw,
-        }
+        outline.into()
     }
 
     /// 将字符转换为轮廓信息（WebAssembly专用）。
@@ -657,15 +890,18 @@ impl FontFace {
     /// 轮廓信息（WasmOutlineInfo）
     pub fn to_outline_of_wasm_glyph_index(&mut self, glyph_index: u32) -> WasmOutlineInfo {
         let outline = self.to_outline_of_glyph_index(glyph_index);
-        let buf = bitcode::serialize(&outline).unwrap();
-        WasmOutlineInfo {
-            buf,
-            units_per_em: outline.units_per_em,
-            advance: outline.advance,
-            bbox: outline.bbox,
-            extents: outline.extents,
-            is_cw: outline.is_cw,
-        }
+        outline.into()
+    }
+
+    /// 按指定的拟合精度将字符转换为轮廓信息（WebAssembly专用）。
+    ///
+    /// # 参数
+    /// - `ch`: 查询的字符
+    /// - `tolerance`: 圆弧拟合贝塞尔曲线的精度，按 units_per_em 缩放
+    /// # 返回值
+    /// 轮廓信息（WasmOutlineInfo）
+    pub fn to_outline_with_tolerance_of_wasm(&mut self, ch: char, tolerance: f32) -> WasmOutlineInfo {
+        self.to_outline_with_tolerance(ch, tolerance).into()
     }
 }
 
@@ -677,6 +913,8 @@ impl FontFace {
 /// - `advance`: 字符的水平进度
 /// - `bbox`: 字形的边界框
 /// - `extents`: 字形的扩展信息
+/// - `max_error`: 圆弧拟合贝塞尔曲线的最大误差
+/// - `fit_failed`: 有曲线超出拟合精度
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
 pub struct WasmOutlineInfo {
     pub buf: Vec<u8>,
@@ -685,6 +923,24 @@ pub struct WasmOutlineInfo {
     pub bbox: Vec<f32>,
     pub extents: Vec<f32>,
     pub is_cw:bool,
+    pub max_error: f32,
+    pub fit_failed: bool,
+}
+
+impl From<OutlineInfo> for WasmOutlineInfo {
+    fn from(outline: OutlineInfo) -> Self {
+        let buf = bitcode::serialize(&outline).unwrap();
+        WasmOutlineInfo {
+            buf,
+            units_per_em: outline.units_per_em,
+            advance: outline.advance,
+            bbox: outline.bbox,
+            extents: outline.extents,
+            is_cw: outline.is_cw,
+            max_error: outline.max_error,
+            fit_failed: outline.fit_failed,
+        }
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -697,4 +953,54 @@ fn default_glyph_index(ch: char) -> u32 {
         }
     }
     glyph_index
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_kerning_pairs() {
+    // Rubik只在GPOS中有字偶距，没有kern表
+    let data = std::fs::read("source/Rubik-VariableFont_wght.ttf").unwrap();
+    let mut face = FontFace::new(Share::new(data));
+    let pairs = face.kerning_pairs("AVTo");
+    let find = |left, right| {
+        pairs
+            .iter()
+            .find(|p| p.left == left && p.right == right)
+            .map(|p| p.advance)
+    };
+    let units_per_em = face.units_per_em as f32;
+    assert_eq!(find('A', 'V'), Some(-46.0 / units_per_em));
+    assert_eq!(find('T', 'o'), Some(-99.0 / units_per_em));
+    assert!(pairs.iter().all(|p| p.advance != 0.0));
+}
+
+#[test]
+fn test_sdf_rect() {
+    let data = std::fs::read("source/SOURCEHANSANSK-MEDIUM.TTF").unwrap();
+    let mut face = FontFace::new(Share::new(data));
+    // 很扁的字形
+    let outline = face.to_outline('一');
+    let arcs = outline.compute_near_arcs_rect(2.0);
+    let info = outline.compute_sdf_tex_rect(arcs.clone(), 32, 2, false, 2);
+    assert_eq!(info.tex_width, 32 + 4);
+    assert!(info.tex_height < 16);
+    assert_eq!(info.sdf_tex.len(), (info.tex_width * info.tex_height) as usize);
+    assert_eq!(info.tex_info.atlas_max_x - info.tex_info.atlas_min_x, 32.0);
+    // 字形中心在笔画内
+    assert!(info.sdf_tex[(info.tex_height / 2 * info.tex_width + info.tex_width / 2) as usize] > 128);
+
+    // wasm接口与直接调用的结果一致
+    let wasm = OutlineInfo::compute_sdf_tex_rect_of_wasm(
+        &bitcode::serialize(&arcs).unwrap(),
+        &outline.extents,
+        outline.units_per_em,
+        outline.advance,
+        32,
+        2,
+        false,
+        2,
+        outline.is_cw,
+    );
+    let wasm: crate::utils::SdfInfo2 = bitcode::deserialize(&wasm).unwrap();
+    assert_eq!(wasm.sdf_tex, info.sdf_tex);
+    assert_eq!((wasm.tex_width, wasm.tex_height), (info.tex_width, info.tex_height));
+}
@@ -0,0 +1,268 @@
+//! glyphy圆弧路径的共享纹理图集
+//!
+//! 把多个字符的索引纹理和数据纹理依次追加到共享的页（`TexData`）中，
+//! 每个字符的`TexInfo`记录其在页中的偏移，整段文字可以用同一对纹理通过glyphy shader绘制。
+//!
+//! 索引纹理按行（shelf）排布，每个字符占`grid_w * grid_h`个像素；
+//! 数据纹理按高为8的列排布（见`BlobArc::encode_data_tex`）。
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use super::blob::{BlobArc, EncodeError, TexData, TexInfo};
+
+/// 图集的一页
+#[derive(Debug, Clone)]
+pub struct GlyphyPage {
+    pub tex: TexData,
+    /// 数据纹理的高度（像素）
+    pub data_tex_height: usize,
+    /// 索引纹理的高度（像素）
+    pub index_tex_height: usize,
+    /// 索引纹理当前行的高度
+    index_row_height: usize,
+}
+
+impl GlyphyPage {
+    fn new(
+        index_tex_width: usize,
+        index_tex_height: usize,
+        data_tex_width: usize,
+        data_tex_height: usize,
+    ) -> Self {
+        let index_len = index_tex_width * index_tex_height;
+        Self {
+            tex: TexData {
+                index_tex: vec![0; index_len * 2],
+                index_offset_x: 0,
+                index_offset_y: 0,
+                index_tex_width,
+                data_tex: vec![0; data_tex_width * data_tex_height * 4],
+                data_offset_x: 0,
+                data_offset_y: 0,
+                data_tex_width,
+                sdf_tex: vec![0; index_len],
+                sdf_tex1: vec![0; index_len / 4],
+                sdf_tex2: vec![0; index_len / 16],
+                sdf_tex3: vec![0; index_len / 64],
+            },
+            data_tex_height,
+            index_tex_height,
+            index_row_height: 0,
+        }
+    }
+
+    /// 为宽w高h的索引块找位置，放不下时返回None，不修改状态
+    fn fit_index(&self, w: usize, h: usize) -> Option<(usize, usize, usize)> {
+        let tex = &self.tex;
+        let (mut x, mut y, mut row_height) =
+            (tex.index_offset_x, tex.index_offset_y, self.index_row_height);
+        if x + w > tex.index_tex_width {
+            x = 0;
+            y += row_height;
+            row_height = 0;
+        }
+        if w > tex.index_tex_width || y + h > self.index_tex_height {
+            return None;
+        }
+        Some((x, y, row_height.max(h)))
+    }
+
+    /// 把字符写入本页
+    fn add(&mut self, blob: &mut BlobArc) -> Result<TexInfo, EncodeError> {
+        let (grid_w, grid_h) = blob.grid_size();
+        let (grid_w, grid_h) = (grid_w as usize, grid_h as usize);
+        let (index_x, index_y, row_height) = self
+            .fit_index(grid_w, grid_h)
+            .ok_or(EncodeError::MemoryOverflow)?;
+
+        // 写入失败时偏移保持不变，已写入的像素落在未使用的区域，之后会被覆盖
+        let tex = &mut self.tex;
+        let (mut data_x, mut data_y) = (tex.data_offset_x, tex.data_offset_y);
+        let data_len = match blob.encode_data_tex(
+            &mut tex.data_tex,
+            tex.data_tex_width,
+            &mut data_x,
+            &mut data_y,
+        ) {
+            Ok(len) => len,
+            // 换行后仍放不下，说明字符的数据比整行还长
+            Err(EncodeError::NewLine) => return Err(EncodeError::MemoryOverflow),
+            Err(err) => return Err(err),
+        };
+
+        let (mut x, mut y) = (index_x, index_y);
+        let mut info = blob.encode_index_tex(
+            &mut tex.index_tex,
+            tex.index_tex_width,
+            &mut x,
+            &mut y,
+            data_len,
+            &mut tex.sdf_tex,
+            &mut tex.sdf_tex1,
+            &mut tex.sdf_tex2,
+            &mut tex.sdf_tex3,
+        )?;
+
+        let extents = blob.get_extents();
+        info.index_offset_x = index_x;
+        info.index_offset_y = index_y;
+        info.data_offset_x = data_x;
+        info.data_offset_y = data_y;
+        info.extents_min_x = extents.min_x;
+        info.extents_min_y = extents.min_y;
+        info.extents_max_x = extents.max_x;
+        info.extents_max_y = extents.max_y;
+
+        tex.index_offset_x = index_x + grid_w;
+        tex.index_offset_y = index_y;
+        self.index_row_height = row_height;
+        tex.data_offset_x = data_x + (data_len + 7) / 8;
+        tex.data_offset_y = data_y;
+
+        Ok(info)
+    }
+}
+
+/// glyphy共享纹理图集
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct GlyphyAtlas {
+    index_tex_width: usize,
+    index_tex_height: usize,
+    data_tex_width: usize,
+    data_tex_height: usize,
+    /// 最大页数，0表示不限制
+    max_pages: usize,
+    pages: Vec<GlyphyPage>,
+}
+
+impl GlyphyAtlas {
+    /// 追加一个字符，当前页放不下时新建一页
+    ///
+    /// # 参数
+    /// * `blob` - `CellInfo::encode_blob_arc`得到的字符数据
+    ///
+    /// # 返回
+    /// * `Result<(usize, TexInfo), EncodeError>` - 所在页和偏移已填好的`TexInfo`；
+    ///   页数已达上限或字符比整页还大时返回`MemoryOverflow`，图集保持不变，调用方可以清空后重建或使用更大的页
+    pub fn add(&mut self, blob: &mut BlobArc) -> Result<(usize, TexInfo), EncodeError> {
+        if let Some(page) = self.pages.last_mut() {
+            match page.add(blob) {
+                Ok(info) => return Ok((self.pages.len() - 1, info)),
+                Err(EncodeError::MemoryOverflow) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if self.max_pages > 0 && self.pages.len() >= self.max_pages {
+            return Err(EncodeError::MemoryOverflow);
+        }
+
+        let mut page = GlyphyPage::new(
+            self.index_tex_width,
+            self.index_tex_height,
+            self.data_tex_width,
+            self.data_tex_height,
+        );
+        let info = page.add(blob)?;
+        self.pages.push(page);
+        Ok((self.pages.len() - 1, info))
+    }
+
+    pub fn pages(&self) -> &[GlyphyPage] {
+        &self.pages
+    }
+
+    pub fn into_pages(self) -> Vec<GlyphyPage> {
+        self.pages
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl GlyphyAtlas {
+    /// 创建图集
+    ///
+    /// # 参数
+    /// * `index_tex_width`、`index_tex_height` - 每页索引纹理的大小
+    /// * `data_tex_width`、`data_tex_height` - 每页数据纹理的大小，高度应为8的倍数
+    /// * `max_pages` - 最大页数，0表示不限制
+    pub fn new(
+        index_tex_width: usize,
+        index_tex_height: usize,
+        data_tex_width: usize,
+        data_tex_height: usize,
+        max_pages: usize,
+    ) -> Self {
+        Self {
+            index_tex_width,
+            index_tex_height,
+            data_tex_width,
+            data_tex_height,
+            max_pages,
+            pages: vec![],
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// 清空所有页
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// 获取某一页的索引纹理
+    pub fn index_tex(&self, page: usize) -> Vec<u8> {
+        self.pages[page].tex.index_tex.clone()
+    }
+
+    /// 获取某一页的数据纹理
+    pub fn data_tex(&self, page: usize) -> Vec<u8> {
+        self.pages[page].tex.data_tex.clone()
+    }
+
+    /// 获取某一页的SDF纹理（与索引纹理同尺寸）
+    pub fn sdf_tex(&self, page: usize) -> Vec<u8> {
+        self.pages[page].tex.sdf_tex.clone()
+    }
+
+    /// 通过 wasm 绑定追加一个字符
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`(usize, TexInfo)`，空间不足时为空
+    pub fn add_of_wasm(&mut self, blob: &mut BlobArc) -> Vec<u8> {
+        match self.add(blob) {
+            Ok(r) => bitcode::serialize(&r).unwrap(),
+            Err(_) => vec![],
+        }
+    }
+}
+
+#[test]
+fn test() {
+    use crate::shape::Circle;
+
+    let cell = Circle::new(50.0, 50.0, 40.0)
+        .unwrap()
+        .get_svg_info()
+        .compute_near_arcs(1.0);
+    let mut blob = cell.encode_blob_arc();
+    let (grid_w, grid_h) = blob.grid_size();
+    let (grid_w, grid_h) = (grid_w as usize, grid_h as usize);
+
+    // 每页只能放下一个字符的索引
+    let mut atlas = GlyphyAtlas::new(grid_w, grid_h, 1024, 8, 1);
+    let (page, info) = atlas.add(&mut blob).unwrap();
+    assert_eq!((page, info.index_offset_x, info.index_offset_y), (0, 0, 0));
+    assert!(matches!(atlas.add(&mut blob), Err(EncodeError::MemoryOverflow)));
+    assert_eq!(atlas.page_count(), 1);
+
+    // 并排放两个，第二个的数据紧跟在第一个之后
+    let mut atlas = GlyphyAtlas::new(grid_w * 2, grid_h, 1024, 8, 0);
+    let (_, info1) = atlas.add(&mut blob).unwrap();
+    let (page, info2) = atlas.add(&mut blob).unwrap();
+    assert_eq!(page, 0);
+    assert_eq!(info2.index_offset_x, grid_w);
+    assert_eq!(info2.data_offset_x, (info1.max_offset + 7) / 8);
+    let (page, _) = atlas.add(&mut blob).unwrap();
+    assert_eq!(page, 1);
+}
@@ -4,8 +4,7 @@
 //! 并将这些数据编码为纹理格式以便GPU渲染。支持WebAssembly目标架构。
 
 use parry2d::math::Vector;
-use std::collections::HashMap;
-// use std::collections::BTreeMap as HashMap;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 #[cfg(target_arch = "wasm32")]
@@ -19,6 +18,7 @@ use super::{
         segment::{PPoint, PSegment},
         vector::VectorEXT,
     },
+    decode::GLYPHY_MAX_NUM_ENDPOINTS,
     sdf::glyphy_sdf_from_arc_list,
     util::{is_inf, GLYPHY_INFINITY},
 };
@@ -33,17 +33,192 @@ const GLYPHY_MAX_D: f32 = 0.5;
 
 const MAX_X: f32 = 4095.;
 const MAX_Y: f32 = 4095.;
+const MAX_COORD_16: f32 = 65535.;
 
 /// 编码过程错误类型
 #[derive(Debug)]
 pub enum EncodeError {
     MemoryOverflow,
     NewLine,
+    /// sdf和偏移超出索引格式能表示的范围
+    IndexOverflow,
+    /// 图集的数据纹理只支持`DataFormat::Rgba8`
+    UnsupportedDataFormat,
+}
+
+/// 索引纹理的编码格式
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum IndexFormat {
+    /// 每格2字节（RG8）：端点数2位 + sdf和偏移14位
+    #[default]
+    U16,
+    /// 每格4字节（RGBA8，a为0）：端点数2位 + sdf和偏移22位，
+    /// 共24位，shader中按float运算也不会丢失精度
+    Wide,
+}
+
+impl IndexFormat {
+    /// 每格的字节数
+    pub fn bytes(&self) -> usize {
+        match self {
+            IndexFormat::U16 => 2,
+            IndexFormat::Wide => 4,
+        }
+    }
+
+    /// sdf和偏移所占的位数
+    pub fn payload_bits(&self) -> u32 {
+        match self {
+            IndexFormat::U16 => 14,
+            IndexFormat::Wide => 22,
+        }
+    }
+
+    /// 计算sdf的梯度等级，使 sdf_index * max_offset + offset 尽量落在格式的范围内
+    pub(crate) fn sdf_level(&self, max_offset: usize) -> usize {
+        ((1usize << self.payload_bits()) / max_offset.max(1))
+            .saturating_sub(1)
+            .max(1)
+    }
+}
+
+/// 数据纹理的编码格式
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DataFormat {
+    /// 每像素4字节（RGBA8）：端点坐标每轴12位
+    #[default]
+    Rgba8,
+    /// 每像素8字节（RGBA16，每通道小端u16）：端点坐标每轴16位，
+    /// 大尺寸的图形量化后仍能保持亚像素精度
+    Rgba16,
+}
+
+impl DataFormat {
+    /// 每像素的字节数
+    pub fn bytes(&self) -> usize {
+        match self {
+            DataFormat::Rgba8 => 4,
+            DataFormat::Rgba16 => 8,
+        }
+    }
+
+    /// 端点坐标量化后的最大值
+    pub fn max_coord(&self) -> f32 {
+        match self {
+            DataFormat::Rgba8 => MAX_X,
+            DataFormat::Rgba16 => MAX_COORD_16,
+        }
+    }
+
+    /// 把点吸附到量化网格上，与数据纹理中解码出的坐标一致
+    pub fn snap(&self, p: &Point, extents: &Aabb) -> Point {
+        let max = self.max_coord();
+        let qx = (max * ((p.x - extents.mins.x) / extents.width())).round();
+        let qy = (max * ((p.y - extents.mins.y) / extents.height())).round();
+        Point::new(
+            qx / max * extents.width() + extents.mins.x,
+            qy / max * extents.height() + extents.mins.y,
+        )
+    }
+
+    /// 将一个圆弧端点编码为一个像素
+    ///
+    /// # 参数
+    /// * `endpoint` - 圆弧端点
+    /// * `extents` - 量化的范围
+    pub fn encode_endpoint(&self, endpoint: &ArcEndpoint, extents: &Aabb) -> [f32; 4] {
+        let max = self.max_coord();
+        let qx = (max * ((endpoint.p[0] - extents.mins.x) / extents.width())).round();
+        let qy = (max * ((endpoint.p[1] - extents.mins.y) / extents.height())).round();
+        match self {
+            DataFormat::Rgba8 => arc_endpoint_encode(qx, qy, endpoint.d),
+            DataFormat::Rgba16 => arc_endpoint_encode16(qx, qy, endpoint.d),
+        }
+    }
+
+    /// 将一条线段编码为一个像素
+    pub fn encode_line(&self, line: Line) -> [f32; 4] {
+        match self {
+            DataFormat::Rgba8 => line_encode(line),
+            DataFormat::Rgba16 => line_encode16(line),
+        }
+    }
+}
+
+/// 默认每个格子最多的圆弧数；每段圆弧最多占2个端点，与shader中 GLYPHY_MAX_NUM_ENDPOINTS (20) 对应
+pub const DEFAULT_MAX_ARCS_PER_CELL: usize = 10;
+
+/// 格子细分的圆弧预算
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CellBudget {
+    /// 每个格子最多的圆弧数
+    pub max_arcs: usize,
+    /// 最小格子为字形尺寸的 1/min_cell_divisor，小于32时按32处理
+    pub min_cell_divisor: f32,
+}
+
+impl Default for CellBudget {
+    fn default() -> Self {
+        Self {
+            max_arcs: DEFAULT_MAX_ARCS_PER_CELL,
+            min_cell_divisor: 128.0,
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl CellBudget {
+    /// 创建圆弧预算
+    ///
+    /// # 参数
+    /// * `max_arcs` - 每个格子最多的圆弧数
+    /// * `min_cell_divisor` - 最小格子为字形尺寸的 1/min_cell_divisor
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new(max_arcs: usize, min_cell_divisor: f32) -> Self {
+        Self {
+            max_arcs,
+            min_cell_divisor,
+        }
+    }
+
+    /// 不限制圆弧数，细分行为与不带预算时一致
+    pub fn unlimited() -> Self {
+        Self {
+            max_arcs: usize::MAX,
+            min_cell_divisor: 32.0,
+        }
+    }
+}
+
+/// 细分到最小格子后圆弧数仍超出预算
+#[derive(Debug, Clone)]
+pub struct CellBudgetError {
+    /// 预算的圆弧数
+    pub max_arcs: usize,
+    /// 超出预算的格子：(圆弧数, 格子范围)
+    pub cells: Vec<(usize, Aabb)>,
+}
+
+impl CellBudgetError {
+    /// 根据细分时记录的超限格子生成结果
+    pub(crate) fn check(budget: &CellBudget, cells: Vec<(usize, Aabb)>) -> Result<(), Self> {
+        if cells.is_empty() {
+            Ok(())
+        } else {
+            Err(Self {
+                max_arcs: budget.max_arcs,
+                cells,
+            })
+        }
+    }
 }
 
 /// 表示单个处理单元的结构体
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UnitArc {
     pub parent_cell: Extents,
     pub offset: usize, // 此单元（去重后）在数据纹理中的 像素偏移（不是字节偏移）；
@@ -63,6 +238,22 @@ pub struct UnitArc {
     pub s_dist_3: u64,
 }
 
+impl UnitArc {
+    // 在数据纹理中占用的像素数，与 encode_data_tex 的写入一致
+    fn data_tex_len(&self) -> usize {
+        match self.data.len() {
+            1 => 1,
+            n if n > 3 => n + 1,
+            n => n,
+        }
+    }
+
+    // shader 中读取数据纹理的像素数：起点 + 逐个端点，超过3个端点时还要读到结束的全零像素
+    fn fetch_count(&self) -> usize {
+        (self.data.len() + 1).clamp(2, GLYPHY_MAX_NUM_ENDPOINTS)
+    }
+}
+
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl UnitArc {
     /// 获取数据长度
@@ -87,7 +278,7 @@ impl UnitArc {
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlobArc {
     pub min_sdf: f32,// 最小SDF值
     pub max_sdf: f32,// 最大SDF值
@@ -102,12 +293,13 @@ pub struct BlobArc {
     pub(crate) data: Vec<Vec<UnitArc>>,// 单元数据矩阵
     pub avg_fetch_achieved: f32,// 平均获取次数
     pub(crate) endpoints: Vec<ArcEndpoint>,
-    pub(crate) data_tex_map: HashMap<u64, u64>, // 数据纹理映射去重
+    pub(crate) data_tex_map: BTreeMap<u64, (usize, usize)>, // 数据纹理去重：键 -> 代表格子在 data 中的 (行, 列)，按键有序保证每次编码的偏移一致
+    pub(crate) data_format: DataFormat, // 数据纹理的编码格式，线段在生成时已按此格式编码
 }
 
 /// 范围描述结构体
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Extents {
     pub min_x: f32,
     pub min_y: f32,
@@ -143,14 +335,30 @@ impl BlobArc {
         self.endpoints[index].clone()
     }
 
-    /// 编码纹理数据
-    pub fn encode_tex(&self) -> SdfInfo {
+    /// 通过 wasm 绑定编码纹理数据，返回bitcode序列化的`SdfInfo`，宽索引也放不下时返回空数组
+    pub fn encode_tex_of_wasm(&self) -> Vec<u8> {
+        match self.try_encode_tex() {
+            Ok(info) => bitcode::serialize(&info).unwrap(),
+            Err(err) => {
+                log::warn!("encode_tex failed: {:?}", err);
+                vec![]
+            }
+        }
+    }
+}
+
+impl BlobArc {
+    /// 编码纹理数据，索引格式见`TexInfo::index_format`
+    ///
+    /// 16位索引放不下时自动改用`IndexFormat::Wide`，宽索引也放不下时返回错误
+    pub fn try_encode_tex(&self) -> Result<SdfInfo, EncodeError> {
         let data_tex = self.encode_data_tex1();
-        let (tex_info, index_tex, sdf_tex1, sdf_tex2, sdf_tex3, sdf_tex4) =
-            self.encode_index_tex1(data_tex.len() / 4);
+        let (mut tex_info, index_tex, sdf_tex1, sdf_tex2, sdf_tex3, sdf_tex4) =
+            self.encode_index_tex1(data_tex.len() / self.data_format.bytes())?;
+        tex_info.data_format = self.data_format;
         let grid_size = self.grid_size();
 
-        SdfInfo {
+        Ok(SdfInfo {
             tex_info,
             data_tex,
             index_tex,
@@ -159,28 +367,66 @@ impl BlobArc {
             sdf_tex3,
             sdf_tex4,
             grid_size: vec![grid_size.0, grid_size.1],
+        })
+    }
+
+    /// 按去重表的顺序给每份数据分配在数据纹理中的偏移，并写入引用它的所有格子
+    pub(crate) fn assign_data_offsets(&mut self) {
+        let mut offsets = BTreeMap::new();
+        let mut len = 0;
+        for (key, &(i, j)) in &self.data_tex_map {
+            offsets.insert(*key, len);
+            len += self.data[i][j].data_tex_len();
+        }
+
+        for row in &mut self.data {
+            for unit_arc in row {
+                if let Some(offset) = offsets.get(&unit_arc.get_key()) {
+                    unit_arc.offset = *offset;
+                }
+            }
+        }
+    }
+
+    /// 每个有数据的最小格子在 shader 中读取数据纹理的像素数
+    pub(crate) fn fetch_counts(&self) -> impl Iterator<Item = usize> + '_ {
+        self.data
+            .iter()
+            .flatten()
+            .filter(|unit_arc| unit_arc.get_key() != u64::MAX)
+            .map(UnitArc::fetch_count)
+    }
+
+    /// 每个格子平均读取数据纹理的像素数
+    pub(crate) fn avg_fetch(&self) -> f32 {
+        let (count, total) = self
+            .fetch_counts()
+            .fold((0, 0), |(count, total), fetch| (count + 1, total + fetch));
+        if count == 0 {
+            0.0
+        } else {
+            total as f32 / count as f32
         }
     }
-}
 
-impl BlobArc {
     // 按数据去重，并编码到纹理
     pub fn encode_data_tex(
         &self,
-        map: &HashMap<u64, u64>,
         data_tex: &mut Vec<u8>,
         data_tex_width: usize,
         offset_x: &mut usize,
         offset_y: &mut usize,
     ) -> Result<usize, EncodeError> {
-        match self.encode_data_tex_impl(map, data_tex, data_tex_width, *offset_x, *offset_y) {
+        if self.data_format != DataFormat::Rgba8 {
+            return Err(EncodeError::UnsupportedDataFormat);
+        }
+        match self.encode_data_tex_impl(data_tex, data_tex_width, *offset_x, *offset_y) {
             Ok(len) => return Ok(len),
             Err(err) => {
                 if let EncodeError::NewLine = err {
                     *offset_x = 0;
                     *offset_y += 8;
                     let len = self.encode_data_tex_impl(
-                        &map,
                         data_tex,
                         data_tex_width,
                         *offset_x,
@@ -198,32 +444,29 @@ impl BlobArc {
         // 返回索引数据和宽高
         let map = &self.data_tex_map;
         let mut len = 0usize;
-        let glyph_width = self.extents.width();
-        let glyph_height = self.extents.height();
+        let format = self.data_format;
         let mut data_tex: Vec<u8> = Vec::with_capacity(map.len());
 
-        for v in map.values() {
-            let unit_arc = unsafe { &mut *(*v as *mut UnitArc) };
-            unit_arc.offset = len;
+        // 偏移已在 assign_data_offsets 中按相同顺序分配
+        for &(i, j) in map.values() {
+            let unit_arc = &self.data[i][j];
             // log::debug!("unit_arc.data.len(): {}", unit_arc.data.len());
             if unit_arc.data.len() == 1 {
                 assert!(unit_arc.data[0].line_encode.is_some());
                 if let Some(data) = &unit_arc.data[0].line_encode {
-                    write_data_tex_by_width(&mut data_tex, data, &mut len);
+                    write_data_tex_by_width(&mut data_tex, data, &mut len, format);
                 }
             } else {
                 for endpoint in &unit_arc.data {
-                    let qx = quantize_x(endpoint.p[0], &self.extents, glyph_width);
-                    let qy = quantize_y(endpoint.p[1], &self.extents, glyph_height);
-                    let rgba = arc_endpoint_encode(qx, qy, endpoint.d);
+                    let rgba = format.encode_endpoint(endpoint, &self.extents);
 
-                    write_data_tex_by_width(&mut data_tex, &rgba, &mut len);
+                    write_data_tex_by_width(&mut data_tex, &rgba, &mut len, format);
                 }
             }
 
             // 单元的端点个数超过 3 个，补充一个全零像素代表结束；
             if unit_arc.data.len() > 3 {
-                write_data_tex_by_width(&mut data_tex, &[0., 0., 0., 0.], &mut len);
+                write_data_tex_by_width(&mut data_tex, &[0., 0., 0., 0.], &mut len, format);
             }
         }
 
@@ -232,7 +475,6 @@ impl BlobArc {
 
     fn encode_data_tex_impl(
         &self,
-        map: &HashMap<u64, u64>,
         data_tex: &mut Vec<u8>,
         data_tex_width: usize,
         offset_x: usize,
@@ -242,9 +484,8 @@ impl BlobArc {
         let glyph_width = self.extents.width();
         let glyph_height = self.extents.height();
 
-        for v in map.values() {
-            let unit_arc = unsafe { &mut *(*v as *mut UnitArc) };
-            unit_arc.offset = len;
+        for &(i, j) in self.data_tex_map.values() {
+            let unit_arc = &self.data[i][j];
 
             if unit_arc.data.len() == 1 {
                 assert!(unit_arc.data[0].line_encode.is_some());
@@ -299,11 +540,8 @@ impl BlobArc {
     ) -> Result<TexInfo, EncodeError> {
         let data_tex_map = &self.data_tex_map;
         let max_offset = data_tex_len;
-        // 计算sdf的 梯度等级
-        let mut level = (2usize.pow(14) / max_offset) - 1;
-        if level < 1 {
-            level = 1;
-        }
+        // 计算sdf的 梯度等级，共享的索引纹理每格2字节，只能使用16位索引
+        let level = IndexFormat::U16.sdf_level(max_offset);
         let sdf_range = self.max_sdf - self.min_sdf + 0.1;
         // 量化：将 sdf_range 分成 level 个区间，看 sdf 落在哪个区间
         let sdf_step = sdf_range / level as f32;
@@ -315,11 +553,8 @@ impl BlobArc {
                 // let unit_arc = &mut row[j];
                 let key = self.data[i][j].get_key();
                 if key != u64::MAX {
-                    let map_arc_data = data_tex_map.get(&key);
-                    if map_arc_data.is_none() {
-                        panic!("unit_arc not found");
-                    }
-                    let map_arc_data = unsafe { &*((*map_arc_data.unwrap()) as *const UnitArc) };
+                    let &(ri, rj) = data_tex_map.get(&key).expect("unit_arc not found");
+                    let map_arc_data = &self.data[ri][rj];
 
                     let mut num_points = map_arc_data.data.len();
 
@@ -341,7 +576,7 @@ impl BlobArc {
                         sdf,
                         self.min_sdf,
                         sdf_step,
-                    );
+                    )?;
                     let offset_x = *offset_x;
                     let offset_y = *offset_y;
 
@@ -422,6 +657,8 @@ impl BlobArc {
 
             min_sdf: self.min_sdf,
             sdf_step,
+            index_format: IndexFormat::U16,
+            data_format: DataFormat::Rgba8,
             char: char::default(),
             index_offset_x: 0,
             index_offset_y: 0,
@@ -438,64 +675,63 @@ impl BlobArc {
         });
     }
 
+    /// 编码单独的索引纹理，16位索引放不下时改用宽索引
     pub fn encode_index_tex1(
         &self,
         data_tex_len: usize,
-    ) -> (TexInfo, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>) {
+    ) -> Result<(TexInfo, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>), EncodeError> {
+        match self.encode_index_tex_with_format(data_tex_len, IndexFormat::U16) {
+            Err(EncodeError::IndexOverflow) => {
+                self.encode_index_tex_with_format(data_tex_len, IndexFormat::Wide)
+            }
+            r => r,
+        }
+    }
+
+    fn encode_index_tex_with_format(
+        &self,
+        data_tex_len: usize,
+        format: IndexFormat,
+    ) -> Result<(TexInfo, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>), EncodeError> {
         let data_tex_map = &self.data_tex_map;
         let max_offset = data_tex_len;
         // 计算sdf的 梯度等级
-        let mut level = (2usize.pow(14) / max_offset) - 1;
-        if level < 1 {
-            level = 1;
-        }
+        let level = format.sdf_level(max_offset);
         let sdf_range = self.max_sdf - self.min_sdf + 0.1;
         // 量化：将 sdf_range 分成 level 个区间，看 sdf 落在哪个区间
         let sdf_step = sdf_range / level as f32;
         let (grid_w, grid_h) = self.grid_size();
         let (grid_w, grid_h) = (grid_w as usize, grid_h as usize);
 
-        let mut index_tex: Vec<u8> = Vec::with_capacity(grid_w * grid_h * 2);
+        let bytes = format.bytes();
+        let mut index_tex: Vec<u8> = vec![0; grid_w * grid_h * bytes];
 
         let sdf_tex: Vec<u8> = Vec::with_capacity(grid_w * grid_h); //阴影用的minimip
         let sdf_tex1: Vec<u8> = Vec::with_capacity((grid_w >> 1) * (grid_h >> 1));
         let sdf_tex2: Vec<u8> = Vec::with_capacity((grid_w >> 2) * (grid_h >> 2));
         let sdf_tex3: Vec<u8> = Vec::with_capacity((grid_w >> 3) * (grid_h >> 3));
 
-        // 2 * grid_w * grid_h 个 Uint8
+        // bytes * grid_w * grid_h 个 Uint8
         for i in 0..self.data.len() {
             let len = self.data[i].len();
             for j in 0..len {
-                // let unit_arc = &mut row[j];
                 let key = self.data[i][j].get_key();
                 if key != u64::MAX {
-                    let map_arc_data = data_tex_map.get(&key);
-                    if map_arc_data.is_none() {
-                        panic!("unit_arc not found");
-                    }
-                    let map_arc_data = unsafe { &*((*map_arc_data.unwrap()) as *const UnitArc) };
+                    let &(ri, rj) = data_tex_map.get(&key).expect("unit_arc not found");
+                    let map_arc_data = &self.data[ri][rj];
 
                     let mut num_points = map_arc_data.data.len();
-
-                    let _num_points2 = num_points;
                     if num_points > 3 {
                         num_points = 0;
                     }
 
                     let offset = map_arc_data.offset;
-                    // log::debug!("offset: {}", offset);
                     let sdf = self.data[i][j].sdf;
 
                     let cell_size = self.cell_size;
                     let is_interval = sdf.abs() <= cell_size * 0.5f32.sqrt();
-                    // log::debug!("================  {:?}",( is_interval,
-                    //     num_points as f32,
-                    //     offset as f32,
-                    //     max_offset as f32,
-                    //     sdf,
-                    //     self.min_sdf,
-                    //     sdf_step,));
-                    let [encode, _] = encode_to_uint16(
+                    let [encode, _] = encode_index(
+                        format,
                         is_interval,
                         num_points as f32,
                         offset as f32,
@@ -503,60 +739,18 @@ impl BlobArc {
                         sdf,
                         self.min_sdf,
                         sdf_step,
-                    );
-                    let mut index = (j + i * grid_w) * 2;
-                    while index > index_tex.len() {
-                        index_tex.push(0);
-                        index -= 0;
-                    }
-                    index_tex.push((encode as i32 & 0xff) as u8);
-                    index_tex.push((encode as i32 >> 8) as u8);
-                    // log::debug!("index_tex[{}][{}]: {} {}", j, i, encode as i32 & 0xff, encode as i32 >> 8);
-                    // sdf_tex.push(self.data[i][j].s_dist);
-
-                    // if i % 2 == 1 && j % 2 == 1 {
-                    //     self.data[i][j].s_dist_1 = (self.data[i][j].s_dist as u64
-                    //         + self.data[i - 1][j].s_dist as u64
-                    //         + self.data[i][j - 1].s_dist as u64
-                    //         + self.data[i - 1][j - 1].s_dist as u64)
-                    //         / 4;
-
-                    //     sdf_tex1.push(self.data[i][j].s_dist_1 as u8);
-
-                    //     if i % 4 == 3 && j % 4 == 3 {
-                    //         self.data[i][j].s_dist_2 = (self.data[i][j].s_dist_1 as u64
-                    //             + self.data[i - 2][j].s_dist_1 as u64
-                    //             + self.data[i][j - 2].s_dist_1 as u64
-                    //             + self.data[i - 2][j - 2].s_dist_1 as u64)
-                    //             / 4;
-
-                    //         sdf_tex2.push(self.data[i][j].s_dist_2 as u8);
-
-                    //         if i % 8 == 7 && j % 8 == 7 {
-                    //             self.data[i][j].s_dist_3 = (self.data[i][j].s_dist_2 as u64
-                    //                 + self.data[i - 4][j].s_dist_2 as u64
-                    //                 + self.data[i][j - 4].s_dist_2 as u64
-                    //                 + self.data[i - 4][j - 4].s_dist_2 as u64)
-                    //                 / 4;
-
-                    //             sdf_tex3.push(self.data[i][j].s_dist_3 as u8);
-                    //         }
-                    //     }
-                    // }
-
-                    // #[cfg(feature = "debug")]
-                    // {
-                    //     unit_arc.show = format!("{}", _num_points2);
-                    // }
+                    )?;
+                    // 小端存储，宽索引的最高字节为0
+                    let index = (j + i * grid_w) * bytes;
+                    index_tex[index..index + bytes]
+                        .copy_from_slice(&(encode as u32).to_le_bytes()[..bytes]);
                 }
             }
         }
 
         let cell_size = self.cell_size;
-        // #[cfg(feature = "debug")]
-        // self.show.push_str(&format!("<br> var max_offset = {:.2}, min_sdf = {:.2}, max_sdf = {:.2}, sdf_step = {:.2}, cell_size = {:.2} <br>", max_offset, self.min_sdf, self.max_sdf, sdf_step, cell_size));
 
-        return (
+        return Ok((
             TexInfo {
                 // unitform
                 cell_size,
@@ -568,6 +762,8 @@ impl BlobArc {
 
                 min_sdf: self.min_sdf,
                 sdf_step,
+                index_format: format,
+                data_format: self.data_format,
                 char: char::default(),
                 index_offset_x: 0,
                 index_offset_y: 0,
@@ -587,10 +783,10 @@ impl BlobArc {
             sdf_tex1,
             sdf_tex2,
             sdf_tex3,
-        );
+        ));
     }
 
-    fn grid_size(&self) -> (f32, f32) {
+    pub(crate) fn grid_size(&self) -> (f32, f32) {
         (
             (self.extents.width() / self.cell_size).round(),
             (self.extents.height() / self.cell_size).round(),
@@ -821,6 +1017,10 @@ pub struct TexInfo {
     pub max_offset: usize,
     pub min_sdf: f32,
     pub sdf_step: f32,
+    /// 索引纹理的编码格式
+    pub index_format: IndexFormat,
+    /// 数据纹理的编码格式
+    pub data_format: DataFormat,
 
     pub index_offset_x: usize,
     pub index_offset_y: usize,
@@ -846,6 +1046,8 @@ impl Default for TexInfo {
             max_offset: Default::default(),
             min_sdf: Default::default(),
             sdf_step: Default::default(),
+            index_format: Default::default(),
+            data_format: Default::default(),
             char: char::default(),
             index_offset_x: Default::default(),
             index_offset_y: Default::default(),
@@ -893,7 +1095,7 @@ pub struct SdfInfo {
 // 返回 u16，从高到低
 // num_points: 2-bit
 // offset + sdf: 14-bit
-// 返回 [encode, sdf_index]
+// 返回 [encode, sdf_index]，超出14位时返回 IndexOverflow
 pub fn encode_to_uint16(
     is_interval: bool, // 圆弧和晶格是否相交；
     num_points: f32,   // 只有 0，1，2，3 四个值
@@ -904,7 +1106,32 @@ pub fn encode_to_uint16(
     sdf: f32,     // 浮点数，介于 [min_sdf, max_sdf] 之间
     min_sdf: f32, // sdf 的 最小值, 为负数表示内部
     sdf_step: f32,
-) -> [f32; 2] {
+) -> Result<[f32; 2], EncodeError> {
+    encode_index(
+        IndexFormat::U16,
+        is_interval,
+        num_points,
+        offset,
+        max_offset,
+        sdf,
+        min_sdf,
+        sdf_step,
+    )
+}
+
+// 按 format 编码索引，从高到低
+// num_points: 2-bit
+// offset + sdf: format.payload_bits() 位
+pub fn encode_index(
+    format: IndexFormat,
+    is_interval: bool,
+    num_points: f32,
+    offset: f32,
+    max_offset: f32,
+    sdf: f32,
+    min_sdf: f32,
+    sdf_step: f32,
+) -> Result<[f32; 2], EncodeError> {
     // 以区间的索引作为sdf的编码
     let mut sdf_index = ((sdf - min_sdf) / sdf_step).floor();
 
@@ -917,20 +1144,17 @@ pub fn encode_to_uint16(
         sdf_index += 2.0;
     }
 
-    // 将 sdf_index 和 offset 编码到一个 uint16 中
+    // 将 sdf_index 和 offset 编码到一个整数中
     // 注：二维坐标 编码成 一维数字的常用做法
     let sdf_and_offset_index = sdf_index * max_offset + offset;
 
-    if sdf_and_offset_index >= (2i32.pow(14)) as f32 {
-        panic!(
-            "Encode error, out of range !, sdf_and_offset_index = {}",
-            sdf_and_offset_index
-        );
+    let bits = format.payload_bits();
+    if sdf_and_offset_index >= (1u32 << bits) as f32 {
+        return Err(EncodeError::IndexOverflow);
     }
 
-    let mut r = ((num_points as i32) << 14) | sdf_and_offset_index as i32;
-    r = r & 0xffff;
-    return [r as f32, sdf_index];
+    let r = ((num_points as u32) << bits) | sdf_and_offset_index as u32;
+    return Ok([r as f32, sdf_index]);
 }
 
 pub struct Res {
@@ -939,12 +1163,19 @@ pub struct Res {
     _sdf: f32,
     pub offset: f32,
 }
-// value: u16，从高到低
+// value: 从高到低
 // num_points: 2-bit
-// offset + sdf: 14-bit
-pub fn decode_from_uint16(value: f32, max_offset: f32, min_sdf: f32, sdf_step: f32) -> Res {
-    let num_points = (value / 16384.0).floor();
-    let sdf_and_offset_index = value % 16384.0;
+// offset + sdf: format.payload_bits() 位（16位索引为14位）
+pub fn decode_from_uint16(
+    value: f32,
+    max_offset: f32,
+    min_sdf: f32,
+    sdf_step: f32,
+    format: IndexFormat,
+) -> Res {
+    let unit = (1u32 << format.payload_bits()) as f32;
+    let num_points = (value / unit).floor();
+    let sdf_and_offset_index = value % unit;
 
     let mut sdf_index = (sdf_and_offset_index / max_offset).floor();
     let offset = sdf_and_offset_index % max_offset;
@@ -1023,6 +1254,7 @@ fn write_data_tex_by_width(
     src_data: &[f32; 4],
     len: &mut usize,
     // data_tex_height: usize,
+    format: DataFormat,
 ) {
     // let (x, y) = get_offset_by_width(*len);
     // let offset = (y + x * 8) * 4;
@@ -1037,10 +1269,19 @@ fn write_data_tex_by_width(
     // data_tex[offset + 2] = src_data[2] as u8;
     // data_tex[offset + 3] = src_data[3] as u8;
 
-    data_tex.push(src_data[0] as u8);
-    data_tex.push(src_data[1] as u8);
-    data_tex.push(src_data[2] as u8);
-    data_tex.push(src_data[3] as u8);
+    match format {
+        DataFormat::Rgba8 => {
+            data_tex.push(src_data[0] as u8);
+            data_tex.push(src_data[1] as u8);
+            data_tex.push(src_data[2] as u8);
+            data_tex.push(src_data[3] as u8);
+        }
+        DataFormat::Rgba16 => {
+            for v in src_data {
+                data_tex.extend((*v as u16).to_le_bytes());
+            }
+        }
+    }
     *len = *len + 1;
 }
 
@@ -1111,6 +1352,32 @@ pub fn arc_endpoint_encode(ix: f32, iy: f32, d: f32) -> [f32; 4] {
     return [r as f32, g, b, a as f32];
 }
 
+// 将 一个圆弧端点 编码为 RGBA16：r 为 d，g、b 为 16 位坐标，a 固定为 0xFFFF，避免与结束的全零像素混淆
+pub fn arc_endpoint_encode16(ix: f32, iy: f32, d: f32) -> [f32; 4] {
+    if ix > MAX_COORD_16 {
+        panic!("ix must be less than or equal to MAX_COORD_16");
+    }
+    if iy > MAX_COORD_16 {
+        panic!("iy must be less than or equal to MAX_COORD_16");
+    }
+    let id;
+    if is_inf(d) {
+        id = 0.0;
+    } else {
+        if d.abs() > GLYPHY_MAX_D {
+            panic!(
+                "d must be less than or equal to GLYPHY_MAX_D, d: {}, GLYPHY_MAX_D: {}",
+                d.abs(),
+                GLYPHY_MAX_D
+            );
+        }
+
+        id = 32768. + (d * 32767.0 / GLYPHY_MAX_D).round();
+    }
+
+    return [id, ix, iy, 65535.0];
+}
+
 pub fn travel_data(data: &Vec<Vec<UnitArc>>) -> [f32; 2] {
     let mut min_sdf = f32::INFINITY;
     let mut max_sdf = -f32::INFINITY;
@@ -1172,6 +1439,46 @@ pub fn line_decode(encoded: [f32; 4], nominal_size: [f32; 2]) -> Line {
 
     return Line::from_normal_d(n, d * scale);
 }
+
+// RGBA16：d 和 角度 各 24 位，高 16 位放在 r / b，低 8 位放在 g / a，
+// 拼起来不超过 2^24，shader 中按 float 运算也不会丢失精度
+pub fn line_encode16(line: Line) -> [f32; 4] {
+    let l = line.normalized();
+
+    let angle = l.n.sdf_angle() as f64;
+    let ia = (-angle / std::f64::consts::PI * 0x7FFFFF as f64).round() as i64;
+    let ua = ia + 0x800000;
+    assert!(0 == (ua & -(0xFFFFFF + 1)));
+
+    let d = l.c as f64;
+    let id = (d * 0x1FFFFF as f64).round() as i64;
+    let ud = id + 0x400000;
+    assert!(0 == (ud & -(0x7FFFFF + 1)));
+    let ud = ud | 0x800000;
+
+    return [
+        (ud >> 8) as f32,
+        (ud & 0xFF) as f32,
+        (ua >> 8) as f32,
+        (ua & 0xFF) as f32,
+    ];
+}
+
+pub fn line_decode16(encoded: [f32; 4], nominal_size: [f32; 2]) -> Line {
+    let ua = encoded[2] * 256.0 + encoded[3];
+    let ia = ua - 0x800000 as f32;
+    let angle = -ia / 0x7FFFFF as f32 * 3.14159265358979;
+
+    let ud = (encoded[0] - 32768.0) * 256.0 + encoded[1];
+
+    let id = ud - 0x400000 as f32;
+    let d = id / 0x1FFFFF as f32;
+    let scale = nominal_size[0].max(nominal_size[1]);
+
+    let n = Vector::new(angle.cos(), angle.sin());
+
+    return Line::from_normal_d(n, d * scale);
+}
 pub fn unsafe_vec_append_slice<T>(vec: &mut Vec<T>, slice: &[T]) {
     unsafe {
         let other = slice as *const [T];
@@ -1201,6 +1508,8 @@ pub fn recursion_near_arcs_of_cell<'a>(
     tempsegment: &mut PSegment,
     // startid: u64,
     tempidxs: &mut Vec<usize>,
+    budget: &CellBudget,
+    overflows: &mut Vec<(usize, Aabb)>,
 ) {
     // let time = std::time::Instant::now();
     let cell_width = cell.width();
@@ -1241,12 +1550,22 @@ pub fn recursion_near_arcs_of_cell<'a>(
 
     let glyph_width = extents.width();
     let glyph_height = extents.height();
+    // 格子边长是字形边长不断二分得到的，按相对字形的比例判断，与字形的缩放无关
+    let reached = |cell: f32, glyph: f32, divisor: f32| cell * divisor <= glyph * 1.001;
+    let at_min = |divisor: f32| {
+        reached(cell_width, glyph_width, divisor) && reached(cell_height, glyph_height, divisor)
+    };
+    // 默认最小格子为字形的 1/32；超出预算时继续细分，直到预算允许的最小格子
+    let at_default_min = at_min(32.0);
     if (
-        arcs.len() <= 2
+        arcs.len() <= budget.max_arcs.min(2)
         // && float_equals(cell_width, cell_height, Some(0.01))
-    ) || (cell_width * 32.0 - glyph_width).abs() < 0.1
-        && (cell_height * 32.0 - glyph_height).abs() < 0.1
+    ) || (at_default_min && arcs.len() <= budget.max_arcs)
+        || at_min(budget.min_cell_divisor.max(32.0))
     {
+        if arcs.len() > budget.max_arcs {
+            overflows.push((arcs.len(), cell.clone()));
+        }
         let mut arcs_index = Vec::with_capacity(arcs.len());
         for arc in arcs {
             // let index = global_arcs.iter().position(|a| a.id == arc.id).unwrap();
@@ -1258,11 +1577,19 @@ pub fn recursion_near_arcs_of_cell<'a>(
         }
         result_arcs.push((arcs_index, cell.clone()));
     } else {
+        let split_col = if at_default_min {
+            // 已到默认最小格子但仍超出预算，按相对字形的比例切较大的一边
+            cell_width / glyph_width >= cell_height / glyph_height
+        } else {
+            (cell_width > cell_height && !reached(cell_width, glyph_width, 32.0))
+            // 非方形范围时，高度已到最小而宽度未到，只能竖切
+            || reached(cell_height, glyph_height, 32.0)
+        };
         let (
             (cell1, cell2),
             (top_near1, bottom_near1, left_near1, right_near1),
             (top_near2, bottom_near2, left_near2, right_near2),
-        ) = if cell_width > cell_height && cell_width * 32.0 > glyph_width {
+        ) = if split_col {
             let (ab1, ab2) = cell.half(Direction::Col);
 
             let col_area = cell.near_area(Direction::Col);
@@ -1327,7 +1654,9 @@ pub fn recursion_near_arcs_of_cell<'a>(
             temps,
             tempsegment,
             // startid,
-            tempidxs
+            tempidxs,
+            budget,
+            overflows,
         );
         recursion_near_arcs_of_cell(
             // global_arcs,
@@ -1344,7 +1673,9 @@ pub fn recursion_near_arcs_of_cell<'a>(
             temps,
             tempsegment,
             // startid,
-            tempidxs
+            tempidxs,
+            budget,
+            overflows,
         );
     }
 }
@@ -1450,3 +1781,47 @@ fn compute_near_arc(
 
     (near_arcs, top_near, bottom_near, left_near, right_near)
 }
+
+#[test]
+fn test() {
+    // 14位放不下时返回错误而不是panic
+    assert!(matches!(
+        encode_to_uint16(true, 2.0, 10.0, 8000.0, 1.0, 0.0, 1.0),
+        Err(EncodeError::IndexOverflow)
+    ));
+    let [value, _] =
+        encode_index(IndexFormat::Wide, true, 2.0, 10.0, 8000.0, 1.0, 0.0, 1.0).unwrap();
+    let r = decode_from_uint16(value, 8000.0, 0.0, 1.0, IndexFormat::Wide);
+    assert_eq!((r.num_points, r.offset, r.is_interval), (2.0, 10.0, true));
+    assert_eq!(r._sdf, 1.0);
+
+    assert_eq!(IndexFormat::U16.sdf_level(20000), 1);
+
+    // 相同输入的编码结果逐字节一致
+    let encode = || {
+        crate::shape::Circle::new(50.0, 50.0, 40.0)
+            .unwrap()
+            .get_svg_info()
+            .compute_near_arcs(1.0)
+            .encode_blob_arc()
+            .try_encode_tex()
+            .unwrap()
+    };
+    let (a, b) = (encode(), encode());
+    assert_eq!(a.data_tex, b.data_tex);
+    assert_eq!(a.index_tex, b.index_tex);
+
+    // 去重表只记录格子位置，克隆后原对象释放也不影响编码
+    fn is_send<T: Send>(_: &T) {}
+    let blob = crate::shape::Circle::new(50.0, 50.0, 40.0)
+        .unwrap()
+        .get_svg_info()
+        .compute_near_arcs(1.0)
+        .encode_blob_arc();
+    let cloned = blob.clone();
+    drop(blob);
+    is_send(&cloned);
+    let c = cloned.try_encode_tex().unwrap();
+    assert_eq!(a.data_tex, c.data_tex);
+    assert_eq!(a.index_tex, c.index_tex);
+}
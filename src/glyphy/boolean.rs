@@ -0,0 +1,552 @@
+//! 圆弧轮廓的布尔运算
+//!
+//! 两组圆弧端点（每组可以有多个轮廓）求并、交、差、异或，结果仍是圆弧端点，可以直接用于`SvgInfo::new`。
+//! 先精确求出所有直线和圆弧之间的交点并在交点处分割，再按每一小段两侧的环绕数决定是否保留，
+//! 最后把保留的小段连接成轮廓。环绕数按非零规则判断内外，没有闭合的轮廓按首尾相连处理。
+//! 为了精度，内部使用f64计算。
+use std::f64::consts::TAU;
+
+use kurbo::{Point as KPoint, Vec2};
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use super::geometry::arc::{arc_midpoint, ArcEndpoint};
+use super::util::{GLYPHY_INFINITY, GLYPHY_MAX_D};
+use crate::Point;
+
+/// 布尔运算的类型
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BooleanOp {
+    /// 并集
+    Union,
+    /// 交集
+    Intersection,
+    /// 差集，第一组减去第二组
+    Difference,
+    /// 异或，只属于其中一组的部分
+    Xor,
+}
+
+impl BooleanOp {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            BooleanOp::Union => a || b,
+            BooleanOp::Intersection => a && b,
+            BooleanOp::Difference => a && !b,
+            BooleanOp::Xor => a != b,
+        }
+    }
+}
+
+/// 两组圆弧端点的布尔运算
+///
+/// 结果轮廓的方向与第一组一致（第一组为空时与第二组一致），外轮廓和洞的方向相反
+///
+/// # 参数
+/// * `a` - 第一组圆弧端点，`d`为`GLYPHY_INFINITY`的端点开始新的轮廓
+/// * `b` - 第二组圆弧端点
+/// * `op` - 运算类型
+///
+/// # 返回
+/// * `Vec<ArcEndpoint>` - 结果的圆弧端点，每段圆弧的`|d|`不超过`GLYPHY_MAX_D`
+pub fn boolean_op(a: &[ArcEndpoint], b: &[ArcEndpoint], op: BooleanOp) -> Vec<ArcEndpoint> {
+    let a = to_edges(a);
+    let b = to_edges(b);
+    let reference = if a.is_empty() { &b } else { &a };
+    let reverse = signed_area(reference) < 0.0;
+
+    let operands = [a, b];
+    let (mut kept, eps) = select(&operands, |w| op.apply(w[0] != 0, w[1] != 0));
+    if reverse {
+        kept = kept.iter().map(Edge::reversed).collect();
+    }
+    to_endpoints(&kept, eps)
+}
+
+/// 通过 wasm 绑定做布尔运算，端点按`[x, y, d, x, y, d, ...]`平铺，与`SvgInfo::new`的参数相同
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn boolean_op_of_wasm(a: &[f32], b: &[f32], op: BooleanOp) -> Vec<f32> {
+    to_flat(&boolean_op(&from_flat(a), &from_flat(b), op))
+}
+
+/// 按`[x, y, d, x, y, d, ...]`平铺的端点
+pub(crate) fn from_flat(v: &[f32]) -> Vec<ArcEndpoint> {
+    v.chunks(3)
+        .map(|v| ArcEndpoint::new(v[0], v[1], v[2]))
+        .collect()
+}
+
+/// 端点按`[x, y, d, x, y, d, ...]`平铺
+pub(crate) fn to_flat(endpoints: &[ArcEndpoint]) -> Vec<f32> {
+    let mut out = Vec::with_capacity(endpoints.len() * 3);
+    for endpoint in endpoints {
+        out.extend_from_slice(&[endpoint.p[0], endpoint.p[1], endpoint.d]);
+    }
+    out
+}
+
+/// 直线或圆弧，圆弧同时记录圆心、半径、起始角和有向的扫过角
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Edge {
+    pub(crate) p0: KPoint,
+    pub(crate) p1: KPoint,
+    pub(crate) d: f64,
+    pub(crate) center: KPoint,
+    pub(crate) radius: f64,
+    start_angle: f64,
+    pub(crate) sweep: f64,
+}
+
+impl Edge {
+    pub(crate) fn new(p0: KPoint, p1: KPoint, d: f64) -> Self {
+        let d = if d.abs() < 1e-9 { 0.0 } else { d };
+        let mut edge = Self {
+            p0,
+            p1,
+            d,
+            center: KPoint::ORIGIN,
+            radius: 0.0,
+            start_angle: 0.0,
+            sweep: 0.0,
+        };
+        if d != 0.0 {
+            // 和 Arc::new 相同：圆心在弦中点沿法向 |弦| / (2 tan(2 atan(d))) 处
+            let dp = p1 - p0;
+            let t = 1.0 / (2.0 * (2.0 * d.atan()).tan());
+            edge.center = p0.midpoint(p1) + Vec2::new(-dp.y, dp.x) * t;
+            edge.radius = (p0 - edge.center).hypot();
+            edge.start_angle = (p0 - edge.center).atan2();
+            // d > 0 时逆时针
+            edge.sweep = 4.0 * d.atan();
+        }
+        edge
+    }
+
+    pub(crate) fn is_line(&self) -> bool {
+        self.d == 0.0
+    }
+
+    pub(crate) fn point(&self, t: f64) -> KPoint {
+        if self.is_line() {
+            self.p0.lerp(self.p1, t)
+        } else {
+            self.center + Vec2::from_angle(self.start_angle + self.sweep * t) * self.radius
+        }
+    }
+
+    /// 参数 t 处的切线，没有单位化
+    pub(crate) fn tangent(&self, t: f64) -> Vec2 {
+        if self.is_line() {
+            self.p1 - self.p0
+        } else {
+            let angle = self.start_angle + self.sweep * t;
+            Vec2::new(-angle.sin(), angle.cos()) * self.sweep
+        }
+    }
+
+    fn length(&self) -> f64 {
+        if self.is_line() {
+            (self.p1 - self.p0).hypot()
+        } else {
+            self.radius * self.sweep.abs()
+        }
+    }
+
+    /// 所在直线或圆上的点对应的参数，不在范围内时可能小于0或大于1
+    fn param(&self, q: KPoint) -> f64 {
+        if self.is_line() {
+            let dp = self.p1 - self.p0;
+            return (q - self.p0).dot(dp) / dp.hypot2();
+        }
+
+        // 按扫过的方向归一化到 [0, 2π) 或 (-2π, 0]
+        let delta = (q - self.center).atan2() - self.start_angle;
+        let delta = if self.sweep > 0.0 {
+            delta.rem_euclid(TAU)
+        } else {
+            -(-delta).rem_euclid(TAU)
+        };
+        let t = delta / self.sweep;
+        // 起点附近的点可能被归一化到整圆的另一端
+        let wrapped = (delta - TAU.copysign(self.sweep)) / self.sweep;
+        if t - 1.0 > -wrapped {
+            wrapped
+        } else {
+            t
+        }
+    }
+
+    fn distance(&self, q: KPoint) -> f64 {
+        let t = self.param(q);
+        if (0.0..=1.0).contains(&t) {
+            if self.is_line() {
+                (q - self.point(t)).hypot()
+            } else {
+                ((q - self.center).hypot() - self.radius).abs()
+            }
+        } else {
+            (q - self.p0).hypot().min((q - self.p1).hypot())
+        }
+    }
+
+    pub(crate) fn reversed(&self) -> Self {
+        Self::new(self.p1, self.p0, -self.d)
+    }
+
+    /// 参数 t0 到 t1 的一段，端点为 q0 和 q1
+    fn sub(&self, t0: f64, q0: KPoint, t1: f64, q1: KPoint) -> Self {
+        if self.is_line() {
+            Self::new(q0, q1, 0.0)
+        } else {
+            Self::new(q0, q1, (self.sweep * (t1 - t0) / 4.0).tan())
+        }
+    }
+}
+
+// 两段所在的直线或圆的交点
+fn candidates(a: &Edge, b: &Edge) -> Vec<KPoint> {
+    match (a.is_line(), b.is_line()) {
+        (true, true) => line_line(a.p0, a.p1, b.p0, b.p1),
+        (true, false) => line_circle(a.p0, a.p1, b.center, b.radius),
+        (false, true) => line_circle(b.p0, b.p1, a.center, a.radius),
+        (false, false) => circle_circle(a.center, a.radius, b.center, b.radius),
+    }
+}
+
+fn line_line(a0: KPoint, a1: KPoint, b0: KPoint, b1: KPoint) -> Vec<KPoint> {
+    let r = a1 - a0;
+    let s = b1 - b0;
+    let denom = r.cross(s);
+    // 平行，重叠的部分由端点在另一段上的判断处理
+    if denom.abs() <= 1e-12 * r.hypot() * s.hypot() {
+        return vec![];
+    }
+    let u = (b0 - a0).cross(s) / denom;
+    vec![a0 + r * u]
+}
+
+fn line_circle(p0: KPoint, p1: KPoint, center: KPoint, radius: f64) -> Vec<KPoint> {
+    let d = p1 - p0;
+    let f = p0 - center;
+    let a = d.dot(d);
+    let b = 2.0 * f.dot(d);
+    let c = f.dot(f) - radius * radius;
+    let delta = b * b - 4.0 * a * c;
+    if delta < 0.0 {
+        return vec![];
+    }
+    let sqrt_delta = delta.sqrt();
+    vec![
+        p0 + d * ((-b - sqrt_delta) / (2.0 * a)),
+        p0 + d * ((-b + sqrt_delta) / (2.0 * a)),
+    ]
+}
+
+fn circle_circle(c0: KPoint, r0: f64, c1: KPoint, r1: f64) -> Vec<KPoint> {
+    let v = c1 - c0;
+    let dist = v.hypot();
+    // 同心，重叠的部分由端点在另一段上的判断处理
+    if dist <= 1e-12 * r0.max(r1) {
+        return vec![];
+    }
+    let a = (r0 * r0 - r1 * r1 + dist * dist) / (2.0 * dist);
+    let h2 = r0 * r0 - a * a;
+    if h2 < 0.0 {
+        return vec![];
+    }
+    let pm = c0 + v * (a / dist);
+    let n = Vec2::new(-v.y, v.x) * (h2.sqrt() / dist);
+    vec![pm + n, pm - n]
+}
+
+/// 点 q 处的环绕数
+///
+/// 直线贡献两端对 q 的张角；圆弧在此基础上，q 在圆弧和弦围成的弓形内时再加上一整圈
+pub(crate) fn winding(edges: &[Edge], q: KPoint) -> i32 {
+    let mut angle = 0.0;
+    for e in edges {
+        let a = e.p0 - q;
+        let b = e.p1 - q;
+        angle += a.cross(b).atan2(a.dot(b));
+
+        if !e.is_line() && (q - e.center).hypot() < e.radius {
+            let chord = e.p1 - e.p0;
+            let mid = e.point(0.5);
+            if chord.cross(q - e.p0) * chord.cross(mid - e.p0) > 0.0 {
+                angle += TAU.copysign(e.sweep);
+            }
+        }
+    }
+    (angle / TAU).round() as i32
+}
+
+/// 有向面积，逆时针为正
+pub(crate) fn signed_area(edges: &[Edge]) -> f64 {
+    let mut area = 0.0;
+    for e in edges {
+        area += 0.5 * e.p0.to_vec2().cross(e.p1.to_vec2());
+        if !e.is_line() {
+            // 弓形的面积
+            area += 0.5 * e.radius * e.radius * (e.sweep - e.sweep.sin());
+        }
+    }
+    area
+}
+
+/// 圆弧端点转为直线和圆弧
+pub(crate) fn to_edges(endpoints: &[ArcEndpoint]) -> Vec<Edge> {
+    to_contours(endpoints).into_iter().flatten().collect()
+}
+
+/// 圆弧端点按轮廓转为直线和圆弧，没有闭合的轮廓补上回到起点的直线
+pub(crate) fn to_contours(endpoints: &[ArcEndpoint]) -> Vec<Vec<Edge>> {
+    fn close(contours: &mut Vec<Vec<Edge>>, mut edges: Vec<Edge>, start: KPoint, prev: KPoint) {
+        if prev != start {
+            edges.push(Edge::new(prev, start, 0.0));
+        }
+        if !edges.is_empty() {
+            contours.push(edges);
+        }
+    }
+
+    let mut contours = vec![];
+    let mut edges = vec![];
+    let mut start_prev: Option<(KPoint, KPoint)> = None;
+    for endpoint in endpoints {
+        let p = KPoint::new(endpoint.p[0] as f64, endpoint.p[1] as f64);
+        match start_prev {
+            Some((start, prev)) if endpoint.d != GLYPHY_INFINITY => {
+                if p != prev {
+                    edges.push(Edge::new(prev, p, endpoint.d as f64));
+                }
+                start_prev = Some((start, p));
+            }
+            _ => {
+                if let Some((start, prev)) = start_prev {
+                    close(&mut contours, std::mem::take(&mut edges), start, prev);
+                }
+                start_prev = Some((p, p));
+            }
+        }
+    }
+    if let Some((start, prev)) = start_prev {
+        close(&mut contours, edges, start, prev);
+    }
+    contours
+}
+
+/// 判断端点重合的距离，随坐标的大小变化
+pub(crate) fn tolerance<'a>(edges: impl IntoIterator<Item = &'a Edge>) -> f64 {
+    let mut scale: f64 = 1.0;
+    for e in edges {
+        scale = scale.max(e.p0.x.abs()).max(e.p0.y.abs());
+        scale = scale.max(e.p1.x.abs()).max(e.p1.y.abs());
+    }
+    scale * 1e-6
+}
+
+/// 在所有交点处分割各组的直线和圆弧，保留两侧内外不同的小段
+///
+/// # 参数
+/// * `operands` - 各组的直线和圆弧
+/// * `inside` - 由各组的环绕数判断是否在结果内
+///
+/// # 返回
+/// * 保留的小段，结果的内部在每段的左侧；以及判断端点重合的距离
+pub(crate) fn select(operands: &[Vec<Edge>], inside: impl Fn(&[i32]) -> bool) -> (Vec<Edge>, f64) {
+    let edges: Vec<&Edge> = operands.iter().flatten().collect();
+
+    let eps = tolerance(edges.iter().copied());
+    // 判断两侧内外时离开小段的距离
+    let nudge = eps * 100.0;
+    let in_range = |e: &Edge, t: f64| {
+        let tol = eps / e.length().max(eps);
+        (-tol..=1.0 + tol).contains(&t)
+    };
+
+    // 各段上的分割点
+    let mut splits: Vec<Vec<KPoint>> = vec![vec![]; edges.len()];
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            let (a, b) = (edges[i], edges[j]);
+            let mut points: Vec<KPoint> = candidates(a, b)
+                .into_iter()
+                .filter(|q| in_range(a, a.param(*q)) && in_range(b, b.param(*q)))
+                .collect();
+            // 端点落在另一段上：T形相接，或者重叠
+            for (x, y) in [(a, b), (b, a)] {
+                for q in [y.p0, y.p1] {
+                    if x.distance(q) < eps {
+                        points.push(q);
+                    }
+                }
+            }
+
+            for q in points {
+                // 靠近端点的交点取端点，保证两段的分割点完全相同
+                let q = [a.p0, a.p1, b.p0, b.p1]
+                    .into_iter()
+                    .find(|end| (q - *end).hypot() < eps)
+                    .unwrap_or(q);
+                splits[i].push(q);
+                splits[j].push(q);
+            }
+        }
+    }
+
+    let mut pieces = vec![];
+    for (e, split) in edges.iter().zip(splits) {
+        let mut split: Vec<(f64, KPoint)> = split
+            .into_iter()
+            .map(|q| (e.param(q).clamp(0.0, 1.0), q))
+            .collect();
+        split.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut points = vec![(0.0, e.p0)];
+        for (t, q) in split {
+            let last = points.last().unwrap().1;
+            if (q - last).hypot() >= eps && (q - e.p1).hypot() >= eps {
+                points.push((t, q));
+            }
+        }
+        points.push((1.0, e.p1));
+        for w in points.windows(2) {
+            pieces.push(e.sub(w[0].0, w[0].1, w[1].0, w[1].1));
+        }
+    }
+
+    let mut kept: Vec<Edge> = vec![];
+    for piece in pieces {
+        let mid = piece.point(0.5);
+        let tangent = piece.tangent(0.5);
+        let normal = Vec2::new(-tangent.y, tangent.x) * (nudge / tangent.hypot());
+        let windings = |q: KPoint| -> Vec<i32> { operands.iter().map(|o| winding(o, q)).collect() };
+        let left = inside(&windings(mid + normal));
+        let right = inside(&windings(mid - normal));
+        if left == right {
+            continue;
+        }
+
+        let e = if left { piece } else { piece.reversed() };
+        // 两组重合的边只保留一条
+        let duplicate = kept.iter().any(|k| {
+            (k.p0 - e.p0).hypot() < eps && (k.p1 - e.p1).hypot() < eps && (k.d - e.d).abs() < 1e-6
+        });
+        if !duplicate {
+            kept.push(e);
+        }
+    }
+    (kept, eps)
+}
+
+/// 首尾相接的小段连接成轮廓，转为圆弧端点；`|d|`超过`GLYPHY_MAX_D`的圆弧从中点分开
+pub(crate) fn to_endpoints(edges: &[Edge], eps: f64) -> Vec<ArcEndpoint> {
+    fn push(result: &mut Vec<ArcEndpoint>, p0: Point, p1: Point, d: f32) {
+        if d.abs() > GLYPHY_MAX_D {
+            let pm = arc_midpoint(p0, p1, d);
+            let half = (d.atan() * 0.5).tan();
+            push(result, p0, pm, half);
+            push(result, pm, p1, half);
+        } else {
+            result.push(ArcEndpoint::new(p1.x, p1.y, d));
+        }
+    }
+    let to_point = |p: KPoint| Point::new(p.x as f32, p.y as f32);
+
+    let mut result = vec![];
+    let mut used = vec![false; edges.len()];
+    for i in 0..edges.len() {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let start = edges[i].p0;
+        let mut contour = vec![edges[i]];
+        let mut current = edges[i].p1;
+        while (current - start).hypot() >= eps {
+            let next = (0..edges.len()).find(|&j| !used[j] && (edges[j].p0 - current).hypot() < eps);
+            match next {
+                Some(j) => {
+                    used[j] = true;
+                    current = edges[j].p1;
+                    contour.push(edges[j]);
+                }
+                None => break,
+            }
+        }
+        let closed = (current - start).hypot() < eps;
+
+        result.push(ArcEndpoint::new(start.x as f32, start.y as f32, GLYPHY_INFINITY));
+        let mut p0 = to_point(start);
+        for (k, e) in contour.iter().enumerate() {
+            // 闭合的轮廓回到起点
+            let p1 = if closed && k == contour.len() - 1 {
+                to_point(start)
+            } else {
+                to_point(e.p1)
+            };
+            push(&mut result, p0, p1, e.d as f32);
+            p0 = p1;
+        }
+    }
+    result
+}
+
+#[test]
+fn test() {
+    use std::f64::consts::PI;
+
+    let area = |endpoints: &[ArcEndpoint]| signed_area(&to_edges(endpoints)).abs();
+
+    // 逆时针的正方形
+    let rect = vec![
+        ArcEndpoint::new(0.0, 0.0, GLYPHY_INFINITY),
+        ArcEndpoint::new(100.0, 0.0, 0.0),
+        ArcEndpoint::new(100.0, 100.0, 0.0),
+        ArcEndpoint::new(0.0, 100.0, 0.0),
+        ArcEndpoint::new(0.0, 0.0, 0.0),
+    ];
+    // 圆心在正方形一角的圆，顺时针
+    let circle = crate::shape::Circle::new(100.0, 100.0, 50.0)
+        .unwrap()
+        .get_arc_endpoints();
+    let quarter = PI * 2500.0 / 4.0;
+
+    for (op, expected) in [
+        (BooleanOp::Union, 10000.0 + 3.0 * quarter),
+        (BooleanOp::Intersection, quarter),
+        (BooleanOp::Difference, 10000.0 - quarter),
+        (BooleanOp::Xor, 10000.0 + 2.0 * quarter),
+    ] {
+        let result = boolean_op(&rect, &circle, op);
+        assert!((area(&result) - expected).abs() < 1.0, "{:?}", op);
+        // 方向与正方形一致
+        assert!(signed_area(&to_edges(&result)) > 0.0);
+        assert!(result.iter().all(|e| e.d == GLYPHY_INFINITY || e.d.abs() <= GLYPHY_MAX_D));
+    }
+
+    // 正方形中间挖洞，洞的方向相反
+    let hole = vec![
+        ArcEndpoint::new(25.0, 25.0, GLYPHY_INFINITY),
+        ArcEndpoint::new(75.0, 25.0, 0.0),
+        ArcEndpoint::new(75.0, 75.0, 0.0),
+        ArcEndpoint::new(25.0, 75.0, 0.0),
+        ArcEndpoint::new(25.0, 25.0, 0.0),
+    ];
+    let result = boolean_op(&rect, &hole, BooleanOp::Difference);
+    assert_eq!(result.iter().filter(|e| e.d == GLYPHY_INFINITY).count(), 2);
+    assert!((signed_area(&to_edges(&result)) - 7500.0).abs() < 1e-3);
+
+    // 与自身求并不变
+    let result = boolean_op(&rect, &rect, BooleanOp::Union);
+    assert!((area(&result) - 10000.0).abs() < 1e-3);
+
+    let flat: Vec<f32> = boolean_op_of_wasm(
+        &[0.0, 0.0, f32::INFINITY, 100.0, 0.0, 0.0, 100.0, 100.0, 0.0, 0.0, 100.0, 0.0, 0.0, 0.0, 0.0],
+        &[],
+        BooleanOp::Union,
+    );
+    assert_eq!(flat.len() % 3, 0);
+}
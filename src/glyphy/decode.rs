@@ -0,0 +1,498 @@
+//! glyphy纹理的CPU参考解码
+//!
+//! 按`source/glyphy.fs`中`glyphy_sdf`的流程读取索引纹理和数据纹理，计算点的有向距离，
+//! 用于在没有GPU的环境中检查`BlobArc::try_encode_tex`和`CellInfo::encode_kd_tree`的编码结果。
+//! 纹理按归一化的浮点数（字节 / 255，RGBA16的数据纹理为 u16 / 65535）读取，与shader的采样一致。
+use super::{
+    blob::{DataFormat, IndexFormat, SdfInfo, TexData, TexInfo},
+    kdtree::{KdSdfInfo, KD_SPLIT_X, KD_SPLIT_Y},
+};
+use crate::{Point, Vector2};
+
+// 与shader中的宏一致
+const GLYPHY_INFINITY: f32 = 1e6;
+const GLYPHY_EPSILON: f32 = 1e-4;
+const GLYPHY_MAX_D: f32 = 0.5;
+pub(crate) const GLYPHY_MAX_NUM_ENDPOINTS: usize = 20;
+
+/// 解码后的索引
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphyIndex {
+    /// 端点的数量，0 代表一直读取到像素为 (0, 0, 0, 0) 的数据为止
+    pub num_endpoints: usize,
+    /// 在数据纹理的偏移，单位：像素
+    pub offset: usize,
+    /// 晶格中心点的sdf，完全在内为 -1e6，完全在外为 1e6
+    pub sdf: f32,
+}
+
+// 从 p0 到 p1 的 圆弧，d = 0 代表线段
+#[derive(Clone, Copy)]
+struct GlyphyArc {
+    p0: Vector2,
+    p1: Vector2,
+    d: f32,
+}
+
+/// 用`BlobArc::try_encode_tex`的结果计算点的有向距离
+///
+/// 数据纹理按像素偏移依次读取（shader中按高为8的列寻址，见`glyphy_sdf_from_page`），
+/// 像素格式见`TexInfo::data_format`。
+///
+/// # 参数
+/// * `info` - 编码结果
+/// * `p` - 网格坐标：原点为包围盒的最小点（extents.mins），单位为格子，范围 [0, grid_w] x [0, grid_h]
+///
+/// # 返回
+/// * `f32` - 有向距离，单位为格子，负数表示在内部
+pub fn glyphy_sdf_from_tex(info: &SdfInfo, p: Point) -> f32 {
+    let tex_info = &info.tex_info;
+    let nominal_size = Vector2::new(tex_info.grid_w, tex_info.grid_h);
+    let p = clamp_to_grid(p, nominal_size);
+
+    let i = p.x as usize + p.y as usize * tex_info.grid_w as usize;
+    let index = decode_glyphy_index(
+        read_index(&info.index_tex, i, tex_info.index_format),
+        tex_info,
+    );
+    let format = tex_info.data_format;
+    glyphy_sdf(p, nominal_size, &index, format, |k| {
+        read_data(&info.data_tex, k, format)
+    })
+}
+
+/// 用图集页（`GlyphyAtlas`）中的纹理计算点的有向距离
+///
+/// # 参数
+/// * `page` - 图集的页纹理
+/// * `info` - 字符在页中的纹理信息
+/// * `p` - 网格坐标，同`glyphy_sdf_from_tex`
+///
+/// # 返回
+/// * `f32` - 有向距离，单位为格子，负数表示在内部
+pub fn glyphy_sdf_from_page(page: &TexData, info: &TexInfo, p: Point) -> f32 {
+    let nominal_size = Vector2::new(info.grid_w, info.grid_h);
+    let p = clamp_to_grid(p, nominal_size);
+
+    let i = (p.x as usize + info.index_offset_x)
+        + (p.y as usize + info.index_offset_y) * page.index_tex_width;
+    let index = decode_glyphy_index(read_index(&page.index_tex, i, info.index_format), info);
+    // 图集的数据纹理总是 RGBA8
+    glyphy_sdf(p, nominal_size, &index, DataFormat::Rgba8, |k| {
+        let x = k / 8 + info.data_offset_x;
+        let y = k % 8 + info.data_offset_y;
+        to_unorm(read_pixel(&page.data_tex, x + y * page.data_tex_width))
+    })
+}
+
+/// 用`CellInfo::encode_kd_tree`的结果计算点的有向距离
+///
+/// 从根节点开始按区域对半查找，叶子的像素按宽索引解码，之后与`glyphy_sdf_from_tex`相同。
+///
+/// # 参数
+/// * `info` - k-d树索引的编码结果
+/// * `p` - 网格坐标，同`glyphy_sdf_from_tex`
+///
+/// # 返回
+/// * `f32` - 有向距离，单位为格子，负数表示在内部
+pub fn glyphy_sdf_from_kd_tex(info: &KdSdfInfo, p: Point) -> f32 {
+    let tex_info = &info.tex_info;
+    let nominal_size = Vector2::new(tex_info.grid_w, tex_info.grid_h);
+    let p = clamp_to_grid(p, nominal_size);
+
+    let (mut min, mut max) = (Vector2::zeros(), nominal_size);
+    let mut node = read_pixel(&info.node_tex, 0);
+    // 每层至少把区域减半，深度不会超过节点数
+    for _ in 0..info.node_count() {
+        let child = u32::from_le_bytes([node[0], node[1], node[2], 0]) as usize;
+        let (axis, second) = match node[3] {
+            KD_SPLIT_X => (0, p.x >= (min.x + max.x) * 0.5),
+            KD_SPLIT_Y => (1, p.y >= (min.y + max.y) * 0.5),
+            _ => break,
+        };
+        let mid = (min[axis] + max[axis]) * 0.5;
+        if second {
+            min[axis] = mid;
+        } else {
+            max[axis] = mid;
+        }
+        node = read_pixel(&info.node_tex, child + second as usize);
+    }
+
+    let index = decode_glyphy_index(node, tex_info);
+    glyphy_sdf(p, nominal_size, &index, DataFormat::Rgba8, |k| {
+        to_unorm(read_pixel(&info.data_tex, k))
+    })
+}
+
+/// 解码索引纹理的一个像素，对应shader中的`decode_glyphy_index`
+///
+/// # 参数
+/// * `c` - 像素的字节，16位索引只使用前两个字节
+/// * `info` - 纹理信息，提供 max_offset, min_sdf, sdf_step 和索引格式
+pub fn decode_glyphy_index(c: [u8; 4], info: &TexInfo) -> GlyphyIndex {
+    let c = to_unorm(c).map(float_to_byte);
+
+    let (num_endpoints, sdf_and_offset_index) = match info.index_format {
+        IndexFormat::U16 => {
+            let value = c[0] + 256 * c[1];
+            (value / 16384, value % 16384)
+        }
+        IndexFormat::Wide => {
+            let value = c[0] + 256 * c[1] + 65536 * c[2];
+            (value / 4194304, value % 4194304)
+        }
+    };
+
+    let max_offset = (info.max_offset as i32).max(1);
+    let sdf_index = sdf_and_offset_index / max_offset;
+    let offset = sdf_and_offset_index - sdf_index * max_offset;
+
+    let sdf = if sdf_index == 0 {
+        // 用 0 表示 完全 在内 的 晶格！
+        -GLYPHY_INFINITY
+    } else if sdf_index == 1 {
+        // 用 1 表示 完全 在外 的 晶格！
+        GLYPHY_INFINITY
+    } else {
+        // 比实际的 sdf 范围多出 2
+        (sdf_index - 2) as f32 * info.sdf_step + info.min_sdf
+    };
+
+    GlyphyIndex {
+        num_endpoints: num_endpoints as usize,
+        offset: offset as usize,
+        sdf,
+    }
+}
+
+// shader 中 uv 最大取 0.99
+fn clamp_to_grid(p: Point, nominal_size: Vector2) -> Vector2 {
+    Vector2::new(
+        p.x.clamp(0.0, nominal_size.x * 0.99),
+        p.y.clamp(0.0, nominal_size.y * 0.99),
+    )
+}
+
+fn read_index(index_tex: &[u8], i: usize, format: IndexFormat) -> [u8; 4] {
+    let bytes = format.bytes();
+    let mut c = [0u8; 4];
+    if let Some(src) = index_tex.get(i * bytes..(i + 1) * bytes) {
+        c[..bytes].copy_from_slice(src);
+    }
+    c
+}
+
+// 越界按 (0, 0, 0, 0) 处理
+fn read_pixel(data_tex: &[u8], i: usize) -> [u8; 4] {
+    match data_tex.get(i * 4..i * 4 + 4) {
+        Some(c) => [c[0], c[1], c[2], c[3]],
+        None => [0; 4],
+    }
+}
+
+// 按数据纹理的格式读取归一化的像素，越界按 (0, 0, 0, 0) 处理
+fn read_data(data_tex: &[u8], i: usize, format: DataFormat) -> [f32; 4] {
+    match format {
+        DataFormat::Rgba8 => to_unorm(read_pixel(data_tex, i)),
+        DataFormat::Rgba16 => match data_tex.get(i * 8..i * 8 + 8) {
+            Some(c) => {
+                let mut v = [0.0; 4];
+                for (k, v) in v.iter_mut().enumerate() {
+                    *v = u16::from_le_bytes([c[k * 2], c[k * 2 + 1]]) as f32 / 65535.0;
+                }
+                v
+            }
+            None => [0.0; 4],
+        },
+    }
+}
+
+fn glyphy_sdf(
+    p: Vector2,
+    nominal_size: Vector2,
+    index: &GlyphyIndex,
+    format: DataFormat,
+    fetch: impl Fn(usize) -> [f32; 4],
+) -> f32 {
+    let mut side = if index.sdf < 0.0 { -1.0 } else { 1.0 };
+    let mut min_dist = GLYPHY_INFINITY;
+
+    let rgba = fetch(index.offset);
+    let mut closest_arc = GlyphyArc {
+        p0: Vector2::zeros(),
+        p1: Vector2::zeros(),
+        d: 0.0,
+    };
+    let (mut pp, _) = arc_endpoint_decode(rgba, nominal_size, format);
+
+    for i in 1..GLYPHY_MAX_NUM_ENDPOINTS {
+        let v = fetch(index.offset + i);
+        if index.num_endpoints == 0 {
+            if v == [0.0; 4] {
+                break;
+            }
+        } else if i >= index.num_endpoints {
+            break;
+        }
+
+        let (p1, d) = arc_endpoint_decode(v, nominal_size, format);
+        let a = GlyphyArc { p0: pp, p1, d };
+
+        // 无穷的 d 代表 Move 语义
+        if d.abs() >= GLYPHY_INFINITY * 0.5 {
+            pp = p1;
+            continue;
+        }
+
+        if arc_wedge_contains(&a, p) {
+            // 处理 尖角
+            let sdist = arc_wedge_signed_dist(&a, p);
+            let udist = sdist.abs() * (1.0 - GLYPHY_EPSILON);
+
+            if udist <= min_dist {
+                min_dist = udist;
+                side = if sdist <= 0.0 { -1.0 } else { 1.0 };
+            }
+        } else {
+            let udist = (p - a.p0).norm().min((p - a.p1).norm());
+
+            if udist < min_dist - GLYPHY_EPSILON {
+                side = 0.0;
+                min_dist = udist;
+                closest_arc = a;
+            } else if side == 0.0 && udist - min_dist <= GLYPHY_EPSILON {
+                let old_ext_dist = arc_extended_dist(&closest_arc, p);
+                let new_ext_dist = arc_extended_dist(&a, p);
+
+                let ext_dist = if new_ext_dist.abs() <= old_ext_dist.abs() {
+                    old_ext_dist
+                } else {
+                    new_ext_dist
+                };
+                side = sign(ext_dist);
+            }
+        }
+        pp = p1;
+    }
+
+    if side == 0.0 {
+        side = sign(arc_extended_dist(&closest_arc, p));
+    }
+
+    // 线段 特殊处理
+    if index.num_endpoints == 1 {
+        let (distance, angle) = decode_line(rgba, nominal_size, format);
+        let n = Vector2::new(angle.cos(), angle.sin());
+
+        side = 1.0;
+        min_dist = (p - nominal_size * 0.5).dot(&n) - distance;
+    }
+
+    min_dist * side
+}
+
+fn to_unorm(c: [u8; 4]) -> [f32; 4] {
+    c.map(|v| v as f32 / 255.0)
+}
+
+// [0, 1] 浮点 --> byte
+fn float_to_byte(v: f32) -> i32 {
+    (v * (256.0 - GLYPHY_EPSILON)) as i32
+}
+
+// [0, 1] 浮点 --> u16
+fn float_to_u16(v: f32) -> i32 {
+    (v * 65535.0).round() as i32
+}
+
+// 与 glsl 的 sign 一致，0 返回 0
+fn sign(v: f32) -> f32 {
+    if v > 0.0 {
+        1.0
+    } else if v < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+// v 的 垂直向量
+fn ortho(v: Vector2) -> Vector2 {
+    Vector2::new(-v.y, v.x)
+}
+
+// returns tan (2 * atan (d))
+fn tan2atan(d: f32) -> f32 {
+    2.0 * d / (1.0 - d * d)
+}
+
+fn arc_center(a: &GlyphyArc) -> Vector2 {
+    (a.p0 + a.p1) * 0.5 + ortho(a.p1 - a.p0) / (2.0 * tan2atan(a.d))
+}
+
+fn arc_wedge_signed_dist_shallow(a: &GlyphyArc, p: Vector2) -> f32 {
+    let v = (a.p1 - a.p0).normalize();
+    let line_d = (p - a.p0).dot(&ortho(v));
+    if a.d == 0.0 {
+        return line_d;
+    }
+
+    let d0 = (p - a.p0).dot(&v);
+    if d0 < 0.0 {
+        return sign(line_d) * (p - a.p0).norm();
+    }
+
+    let d1 = (a.p1 - p).dot(&v);
+    if d1 < 0.0 {
+        return sign(line_d) * (p - a.p1).norm();
+    }
+
+    let r = 2.0 * a.d * (d0 * d1) / (d0 + d1);
+    if r * line_d > 0.0 {
+        return sign(line_d)
+            * (line_d + r)
+                .abs()
+                .min((p - a.p0).norm().min((p - a.p1).norm()));
+    }
+
+    line_d + r
+}
+
+fn arc_wedge_signed_dist(a: &GlyphyArc, p: Vector2) -> f32 {
+    if a.d.abs() <= 0.03 {
+        return arc_wedge_signed_dist_shallow(a, p);
+    }
+
+    let c = arc_center(a);
+    sign(a.d) * ((a.p0 - c).norm() - (p - c).norm())
+}
+
+// 解码 arc 端点，返回 (p, d)
+fn arc_endpoint_decode(v: [f32; 4], nominal_size: Vector2, format: DataFormat) -> (Vector2, f32) {
+    let (p, d) = match format {
+        DataFormat::Rgba8 => {
+            let f = float_to_byte(v[3]);
+            let nimbles = Vector2::new((f / 16) as f32, (f % 16) as f32);
+            let p = (nimbles + Vector2::new(v[1], v[2])) / 16.0;
+            (p, (float_to_byte(v[0]) - 128) as f32 * GLYPHY_MAX_D / 127.0)
+        }
+        // g、b 直接是归一化的坐标
+        DataFormat::Rgba16 => (
+            Vector2::new(v[1], v[2]),
+            (float_to_u16(v[0]) - 32768) as f32 * GLYPHY_MAX_D / 32767.0,
+        ),
+    };
+    let d = if v[0] == 0.0 { GLYPHY_INFINITY } else { d };
+
+    (p.component_mul(&nominal_size), d)
+}
+
+// 判断是否 尖角内；glsl 中 向量 * mat2 按列点乘
+fn arc_wedge_contains(a: &GlyphyArc, p: Vector2) -> bool {
+    let d2 = tan2atan(a.d);
+    let v = a.p1 - a.p0;
+
+    (p - a.p0).dot(&Vector2::new(v.x + d2 * v.y, -d2 * v.x + v.y)) >= 0.0
+        && (p - a.p1).dot(&Vector2::new(v.x - d2 * v.y, d2 * v.x + v.y)) <= 0.0
+}
+
+// 点 到 圆弧 的 距离（不处理尖角内的点）
+fn arc_extended_dist(a: &GlyphyArc, p: Vector2) -> f32 {
+    let m = (a.p0 + a.p1) * 0.5;
+    let d2 = tan2atan(a.d);
+    let v = a.p1 - a.p0;
+
+    if (p - m).dot(&(a.p1 - m)) < 0.0 {
+        (p - a.p0).dot(&Vector2::new(d2 * v.x - v.y, v.x + d2 * v.y).normalize())
+    } else {
+        (p - a.p1).dot(&Vector2::new(-d2 * v.x - v.y, v.x - d2 * v.y).normalize())
+    }
+}
+
+// 解码线段，返回 (distance, angle)
+fn decode_line(v: [f32; 4], nominal_size: Vector2, format: DataFormat) -> (f32, f32) {
+    let (angle, d) = match format {
+        DataFormat::Rgba8 => {
+            let iv = v.map(float_to_byte);
+
+            let ua = iv[2] * 256 + iv[3];
+            let ia = ua - 0x8000;
+            let angle = -(ia as f32) / 0x7FFF as f32 * 3.14159265358979;
+
+            let ud = (iv[0] - 128) * 256 + iv[1];
+            let id = ud - 0x4000;
+            (angle, id as f32 / 0x1FFF as f32)
+        }
+        // 高 16 位在 r / b，低 8 位在 g / a
+        DataFormat::Rgba16 => {
+            let iv = v.map(float_to_u16);
+
+            let ua = iv[2] * 256 + iv[3];
+            let ia = ua - 0x800000;
+            let angle = -(ia as f32) / 0x7FFFFF as f32 * 3.14159265358979;
+
+            let ud = (iv[0] - 0x8000) * 256 + iv[1];
+            let id = ud - 0x400000;
+            (angle, id as f32 / 0x1FFFFF as f32)
+        }
+    };
+
+    (d * nominal_size.x.max(nominal_size.y), angle)
+}
+
+#[test]
+fn test() {
+    use crate::glyphy::sdf::glyphy_sdf_from_arc_list;
+    use crate::shape::{Circle, Polygon};
+
+    let shapes = [
+        Circle::new(50.0, 50.0, 40.0).unwrap().get_svg_info(),
+        // 斜边的格子只有一条线段，走线段的编码
+        Polygon::new(vec![10.0, 10.0, 90.0, 30.0, 40.0, 90.0]).get_svg_info(),
+    ];
+    for svg_info in &shapes {
+        let cell = svg_info.compute_near_arcs(1.0);
+        let data_len = cell.encode_blob_arc().try_encode_tex().unwrap().data_tex.len();
+        for format in [DataFormat::Rgba8, DataFormat::Rgba16] {
+            let blob = cell.encode_blob_arc_with_format(format);
+            let sdf_info = blob.try_encode_tex().unwrap();
+            assert_eq!(sdf_info.tex_info.data_format, format);
+            assert_eq!(sdf_info.data_tex.len(), data_len / 4 * format.bytes());
+
+            let (grid_w, grid_h) = blob.grid_size();
+            let cell_w = blob.extents.width() / grid_w;
+            let cell_h = blob.extents.height() / grid_h;
+
+            let mut checked = 0;
+            for j in 0..grid_h as usize {
+                for i in 0..grid_w as usize {
+                    let p = Point::new(i as f32 + 0.5, j as f32 + 0.5);
+                    let glyph_p = Point::new(
+                        blob.extents.mins.x + p.x * cell_w,
+                        blob.extents.mins.y + p.y * cell_h,
+                    );
+                    let expected =
+                        glyphy_sdf_from_arc_list(&svg_info.arc_endpoints, glyph_p).0 / cell_w;
+                    // 只有靠近轮廓的格子保证存放了最近的圆弧
+                    if expected.abs() > 1.0 {
+                        continue;
+                    }
+
+                    let d = glyphy_sdf_from_tex(&sdf_info, p);
+                    assert!(
+                        (d - expected).abs() < 0.1,
+                        "{:?} cell ({}, {}): decoded {}, expected {}",
+                        format,
+                        i,
+                        j,
+                        d,
+                        expected
+                    );
+                    checked += 1;
+                }
+            }
+            assert!(checked > 0);
+        }
+    }
+}
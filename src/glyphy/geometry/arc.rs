@@ -38,6 +38,12 @@ pub fn tan2atan(d: f32) -> f32 {
     return 2. * d / (1. - d * d);
 }
 
+// 圆弧 p0 -> p1 的中点，在弦中点外 |弦| * d / 2 处
+pub fn arc_midpoint(p0: Point, p1: Point, d: f32) -> Point {
+    let dp = p1 - p0;
+    return p0 + dp.scale(0.5) - dp.ortho().scale(d * 0.5);
+}
+
 pub struct ErrorValue {
     pub(crate) value: f32,
 }
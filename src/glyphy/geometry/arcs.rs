@@ -9,11 +9,32 @@ use crate::glyphy::arc_bezier::{
     arc_bezier_error_approximator_default, ArcBezierApproximatorQuantized,
 };
 use crate::glyphy::geometry::point::PointExt;
-use crate::glyphy::util::{is_zero, GLYPHY_INFINITY, GLYPHY_MAX_D};
+use crate::glyphy::util::{is_zero, GLYPHY_EPSILON, GLYPHY_INFINITY, GLYPHY_MAX_D};
 use crate::Point;
+use parry2d::math::Vector;
 // #[cfg(target_arch = "wasm32")]
 // use wasm_bindgen::prelude::wasm_bindgen;
 
+/// 圆弧拟合3次贝塞尔曲线的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArcApproximator {
+    /// 弹簧系统迭代调整分段位置（移植自glyphy），圆弧较多，速度较慢
+    #[default]
+    SpringSystem,
+    /// 在拐点处分割后，用切线连续的双圆弧拟合，超出精度时二分
+    Biarc,
+}
+
+/// 同一条曲线用两种方式拟合的圆弧数和误差，
+/// 误差都是曲线上采样点到圆弧的最大距离
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArcApproximatorComparison {
+    pub spring_system_arcs: usize,
+    pub spring_system_error: f32,
+    pub biarc_arcs: usize,
+    pub biarc_error: f32,
+}
+
 // #[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
 #[derive(Clone)]
 pub struct GlyphyArcAccumulator {
@@ -26,6 +47,7 @@ pub struct GlyphyArcAccumulator {
     pub(crate) num_endpoints: f32,
     pub(crate) max_error: f32,
     pub(crate) success: bool,
+    pub(crate) approximator: ArcApproximator,
     pub result: Vec<ArcEndpoint>,
 }
 
@@ -41,6 +63,7 @@ impl GlyphyArcAccumulator {
             num_endpoints: 0.0,
             max_error: 0.0,
             success: true,
+            approximator: ArcApproximator::SpringSystem,
             result: Vec::new(),
         };
 
@@ -48,6 +71,70 @@ impl GlyphyArcAccumulator {
         res
     }
 
+    /// 圆弧拟合贝塞尔曲线的精度，值越小越精确，圆弧也越多
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    /// 设置拟合精度，对之后添加的曲线生效
+    pub fn set_tolerance(&mut self, tolerance: f32) {
+        self.tolerance = tolerance;
+    }
+
+    /// 已拟合曲线的最大误差
+    pub fn max_error(&self) -> f32 {
+        self.max_error
+    }
+
+    /// 所有曲线的拟合误差都在精度以内
+    pub fn success(&self) -> bool {
+        self.success
+    }
+
+    /// 圆弧拟合3次贝塞尔曲线的方式
+    pub fn approximator(&self) -> ArcApproximator {
+        self.approximator
+    }
+
+    /// 设置拟合方式，对之后添加的曲线生效
+    pub fn set_approximator(&mut self, approximator: ArcApproximator) {
+        self.approximator = approximator;
+    }
+
+    /// 用两种方式拟合同一条曲线，比较圆弧数和误差，不影响累积的结果
+    pub fn compare_approximators(&self, b: &Bezier) -> ArcApproximatorComparison {
+        let appx = ArcBezierApproximatorQuantized::new(Some(self.max_d), Some(self.d_bits));
+        let mut spring_system = vec![];
+        ArcsBezierApproximatorSpringSystem::approximate_bezier_with_arcs(
+            b,
+            self.tolerance,
+            &appx,
+            &mut spring_system,
+            Some(self.spring_max_segments(b)),
+        );
+        // 量化时直接修改了 d，圆心和半径需要重新计算
+        let spring_system: Vec<Arc> = spring_system
+            .iter()
+            .map(|a| Arc::new(a.p0, a.p1, a.d))
+            .collect();
+
+        let mut biarc = vec![];
+        ArcsBezierApproximatorBiarc::approximate_bezier_with_arcs(
+            b,
+            self.tolerance,
+            self.max_d,
+            self.d_bits,
+            &mut biarc,
+        );
+
+        ArcApproximatorComparison {
+            spring_system_arcs: spring_system.len(),
+            spring_system_error: bezier_arcs_error(b, &spring_system, COMPARE_SAMPLES),
+            biarc_arcs: biarc.len(),
+            biarc_error: bezier_arcs_error(b, &biarc, COMPARE_SAMPLES),
+        }
+    }
+
     pub fn reset(&mut self) {
         self.current_point = Point::new(0.0, 0.0);
         self.start_point = self.current_point;
@@ -138,21 +225,48 @@ impl GlyphyArcAccumulator {
         self.accumulate(p1, d);
     }
 
+    // 弹簧系统拟合的分段数上限
+    // d 量化后每段的误差最多为 量化步长 / 2 * 弦长 / 2，精度要求高时默认的100段不够，按控制多边形的长度估算所需的段数
+    fn spring_max_segments(&self, b: &Bezier) -> i32 {
+        if self.d_bits == 0 || self.max_d == 0. || !self.max_d.is_finite() {
+            return 100;
+        }
+        let step = self.max_d / ((1 << (self.d_bits - 1) as u32) - 1) as f32;
+        let len = (b.p1 - b.p0).norm() + (b.p2 - b.p1).norm() + (b.p3 - b.p2).norm();
+        let n = (len * step / (4.0 * self.tolerance)).ceil() as i32 + 1;
+        n.clamp(100, 1000)
+    }
+
     // 圆弧 拟合 贝塞尔
     pub fn bezier(&mut self, b: Bezier) {
-        let appx = ArcBezierApproximatorQuantized::new(Some(self.max_d), Some(self.d_bits));
-
         // 圆弧 拟合 贝塞尔 的 主要实现
         let mut arcs = vec![];
-        let e = ArcsBezierApproximatorSpringSystem::approximate_bezier_with_arcs(
-            &b,
-            self.tolerance,
-            &appx,
-            &mut arcs,
-            None,
-        );
+        let e = match self.approximator {
+            ArcApproximator::SpringSystem => {
+                let appx =
+                    ArcBezierApproximatorQuantized::new(Some(self.max_d), Some(self.d_bits));
+                ArcsBezierApproximatorSpringSystem::approximate_bezier_with_arcs(
+                    &b,
+                    self.tolerance,
+                    &appx,
+                    &mut arcs,
+                    Some(self.spring_max_segments(&b)),
+                )
+            }
+            ArcApproximator::Biarc => ArcsBezierApproximatorBiarc::approximate_bezier_with_arcs(
+                &b,
+                self.tolerance,
+                self.max_d,
+                self.d_bits,
+                &mut arcs,
+            ),
+        };
 
         self.max_error = self.max_error.max(e);
+        // 分段数达到上限仍超出精度
+        if e > self.tolerance {
+            self.success = false;
+        }
 
         self.move_to(b.p0);
         for i in 0..arcs.len() {
@@ -317,3 +431,273 @@ impl ArcsBezierApproximatorSpringSystem {
         return max_e;
     }
 }
+
+// 双圆弧拟合时最多二分的层数
+const BIARC_MAX_DEPTH: u32 = 16;
+// 双圆弧拟合时每段曲线上的采样数
+const BIARC_SAMPLES: usize = 16;
+// 比较两种拟合方式时整条曲线上的采样数
+const COMPARE_SAMPLES: usize = 64;
+
+pub struct ArcsBezierApproximatorBiarc;
+
+impl ArcsBezierApproximatorBiarc {
+    // 圆弧 拟合 3次 Bezier
+    // 返回 最大误差
+    pub fn approximate_bezier_with_arcs(
+        b: &Bezier,
+        tolerance: f32,
+        max_d: f32,
+        d_bits: i32,
+        arcs: &mut Vec<Arc>,
+    ) -> f32 {
+        arcs.clear();
+
+        let v1 = b.p1 - (b.p0);
+        let v2 = b.p2 - (b.p0);
+        let v3 = b.p3 - (b.p0);
+        if is_zero(v1.sdf_cross(&v2), None) && is_zero(v2.sdf_cross(&v3), None) {
+            // 如果所有点都在一条直线上, 只生成一条直线
+            if !b.p0.equals(&b.p3) {
+                arcs.push(Arc::new(b.p0, b.p3, 0.0));
+            }
+            return 0.0;
+        }
+
+        // 拐点两侧曲率符号相反，双圆弧无法拟合，先在拐点处分割
+        let mut t = vec![0.0];
+        t.extend(Self::inflections(b));
+        t.push(1.0);
+
+        let mut max_e: f32 = 0.0;
+        for i in 0..t.len() - 1 {
+            if t[i + 1] - t[i] < GLYPHY_EPSILON {
+                continue;
+            }
+            let segment = b.segment(t[i], t[i + 1]);
+            let e = Self::fit(&segment, tolerance, max_d, d_bits, 0, arcs);
+            max_e = max_e.max(e);
+        }
+        return max_e;
+    }
+
+    // 曲线在 (0, 1) 内的拐点，即 B'(t) × B''(t) = 0 的根，从小到大排列
+    fn inflections(b: &Bezier) -> Vec<f32> {
+        // B'(t) / 3 = a t² + b t + c
+        let a = (b.p3 - b.p0) + (b.p1 - b.p2).scale(3.0);
+        let bb = ((b.p0 - b.p1) + (b.p2 - b.p1)).scale(2.0);
+        let c = b.p1 - b.p0;
+
+        // (a t² + b t + c) × (2a t + b) = -(a×b) t² + 2(c×a) t + c×b
+        let qa = -a.sdf_cross(&bb);
+        let qb = 2.0 * c.sdf_cross(&a);
+        let qc = c.sdf_cross(&bb);
+
+        let mut roots = vec![];
+        if qa.abs() < 1e-9 {
+            if qb.abs() > 1e-9 {
+                roots.push(-qc / qb);
+            }
+        } else {
+            let delta = qb * qb - 4.0 * qa * qc;
+            if delta >= 0.0 {
+                let sqrt_delta = delta.sqrt();
+                roots.push((-qb - sqrt_delta) / (2.0 * qa));
+                roots.push((-qb + sqrt_delta) / (2.0 * qa));
+            }
+        }
+
+        roots.retain(|t| *t > GLYPHY_EPSILON && *t < 1.0 - GLYPHY_EPSILON);
+        roots.sort_by(|a, b| a.total_cmp(b));
+        return roots;
+    }
+
+    // 用双圆弧拟合没有拐点的曲线，超出精度或 d 超出 max_d 时二分
+    // 返回 最大误差
+    fn fit(
+        b: &Bezier,
+        tolerance: f32,
+        max_d: f32,
+        d_bits: i32,
+        depth: u32,
+        arcs: &mut Vec<Arc>,
+    ) -> f32 {
+        let t0 = Self::end_tangent(&[b.p1 - b.p0, b.p2 - b.p0, b.p3 - b.p0]);
+        let t1 = Self::end_tangent(&[b.p3 - b.p2, b.p3 - b.p1, b.p3 - b.p0]);
+        let (t0, t1) = match (t0, t1) {
+            (Some(t0), Some(t1)) => (t0, t1),
+            // 所有点重合
+            _ => return 0.0,
+        };
+
+        match Self::biarc(b.p0, t0, b.p3, t1) {
+            Some((pm, d0, d1)) if d0.abs() <= max_d && d1.abs() <= max_d => {
+                let biarc = [
+                    Arc::new(b.p0, pm, quantize_d(d0, max_d, d_bits)),
+                    Arc::new(pm, b.p3, quantize_d(d1, max_d, d_bits)),
+                ];
+                let e = bezier_arcs_error(b, &biarc, BIARC_SAMPLES);
+                if e <= tolerance || depth >= BIARC_MAX_DEPTH {
+                    arcs.extend(biarc);
+                    return e;
+                }
+            }
+            _ if depth >= BIARC_MAX_DEPTH => {
+                let line = [Arc::new(b.p0, b.p3, 0.0)];
+                let e = bezier_arcs_error(b, &line, BIARC_SAMPLES);
+                arcs.extend(line);
+                return e;
+            }
+            _ => {}
+        }
+
+        let (first, second) = b.halve();
+        let e0 = Self::fit(&first, tolerance, max_d, d_bits, depth + 1, arcs);
+        let e1 = Self::fit(&second, tolerance, max_d, d_bits, depth + 1, arcs);
+        return e0.max(e1);
+    }
+
+    // 端点处的单位切线，控制点与端点重合时依次退化到更远的控制点
+    fn end_tangent(candidates: &[Vector<f32>]) -> Option<Vector<f32>> {
+        candidates
+            .iter()
+            .find(|v| v.norm() > GLYPHY_EPSILON)
+            .map(|v| v.normalize())
+    }
+
+    // 起点 p0、终点 p1 和两端单位切线 t0、t1 确定的双圆弧，两段圆弧的两个 d 相等
+    // 返回 (连接点, 第一段圆弧的 d, 第二段圆弧的 d)，无法构造时返回 None
+    fn biarc(p0: Point, t0: Vector<f32>, p1: Point, t1: Vector<f32>) -> Option<(Point, f32, f32)> {
+        let v = p1 - p0;
+        let t = t0 + t1;
+        let vt = v.dot(&t);
+        let denom = 2.0 * (1.0 - t0.dot(&t1));
+
+        let d = if denom < 1e-6 {
+            // 两端切线平行
+            let vt1 = v.dot(&t1);
+            if vt1.abs() < 1e-6 {
+                return None;
+            }
+            v.dot(&v) / (4.0 * vt1)
+        } else {
+            (-vt + (vt * vt + denom * v.dot(&v)).sqrt()) / denom
+        };
+        if d <= 0.0 {
+            return None;
+        }
+
+        let q0 = p0 + t0.scale(d);
+        let q1 = p1 - t1.scale(d);
+        let pm = q0.midpoint(&q1);
+        let tm = q1 - q0;
+        if tm.norm() < GLYPHY_EPSILON {
+            return None;
+        }
+
+        let d0 = Self::arc_d(t0, pm - p0)?;
+        let d1 = Self::arc_d(tm.normalize(), p1 - pm)?;
+        return Some((pm, d0, d1));
+    }
+
+    // 起点单位切线为 tangent、弦为 chord 的圆弧的 d = tan(α / 2)，α 是切线到弦的有向夹角
+    fn arc_d(tangent: Vector<f32>, chord: Vector<f32>) -> Option<f32> {
+        let len = chord.norm();
+        let s = len + tangent.dot(&chord);
+        if len < GLYPHY_EPSILON || s <= len * 1e-6 {
+            return None;
+        }
+        return Some(tangent.sdf_cross(&chord) / s);
+    }
+}
+
+// 按 d_bits 量化 d，和 ArcBezierApproximatorQuantized 一致
+fn quantize_d(d: f32, max_d: f32, d_bits: i32) -> f32 {
+    if d_bits == 0 || max_d == 0. || !max_d.is_finite() {
+        return d;
+    }
+    let mult = ((1 << (d_bits - 1) as u32) - 1) as f32;
+    return (d / max_d * mult).round() * max_d / mult;
+}
+
+/// 曲线上均匀采样（不含两端）的点到圆弧的最大距离
+/// # 参数
+/// - `b`: 贝塞尔曲线
+/// - `arcs`: 拟合曲线的圆弧
+/// - `samples`: 采样分段数
+pub fn bezier_arcs_error(b: &Bezier, arcs: &[Arc], samples: usize) -> f32 {
+    let mut e: f32 = 0.0;
+    for i in 1..samples {
+        let p = b.point(i as f32 / samples as f32);
+        let d = arcs
+            .iter()
+            .map(|a| a.distance_to_point(p).abs())
+            .fold(GLYPHY_INFINITY, f32::min);
+        e = e.max(d);
+    }
+    return e;
+}
+
+#[test]
+fn test_biarc() {
+    let curves = [
+        // 对称的拱形
+        Bezier::new(
+            Point::new(0., 0.),
+            Point::new(0., 100.),
+            Point::new(100., 100.),
+            Point::new(100., 0.),
+        ),
+        // 一个拐点
+        Bezier::new(
+            Point::new(0., 0.),
+            Point::new(100., 100.),
+            Point::new(0., -100.),
+            Point::new(100., 0.),
+        ),
+        // 尖点
+        Bezier::new(
+            Point::new(0., 0.),
+            Point::new(100., 100.),
+            Point::new(0., 100.),
+            Point::new(100., 0.),
+        ),
+        // 控制点与起点重合
+        Bezier::new(
+            Point::new(0., 0.),
+            Point::new(0., 0.),
+            Point::new(100., 100.),
+            Point::new(100., 0.),
+        ),
+    ];
+
+    for b in curves {
+        let mut spring_system = GlyphyArcAccumulator::new();
+        spring_system.set_tolerance(0.01);
+        let comparison = spring_system.compare_approximators(&b);
+        // 两种方式在整条曲线上的实际误差都在精度以内
+        assert!(comparison.spring_system_error <= spring_system.tolerance());
+        assert!(comparison.biarc_error <= spring_system.tolerance());
+
+        // 比较结果与实际拟合的圆弧数一致
+        spring_system.bezier(Bezier::new(b.p0, b.p1, b.p2, b.p3));
+        assert_eq!(spring_system.result.len() - 1, comparison.spring_system_arcs);
+
+        let mut accumulate = GlyphyArcAccumulator::new();
+        accumulate.set_tolerance(0.01);
+        accumulate.set_approximator(ArcApproximator::Biarc);
+        accumulate.bezier(b);
+        assert!(accumulate.success());
+        assert!(accumulate.max_error() <= accumulate.tolerance());
+        assert_eq!(accumulate.result.len() - 1, comparison.biarc_arcs);
+
+        let result = &accumulate.result;
+        assert_eq!(result[0].d, GLYPHY_INFINITY);
+        assert!(result.len() > 1);
+        for endpoint in &result[1..] {
+            assert!(endpoint.d.abs() <= GLYPHY_MAX_D);
+        }
+        let last = result.last().unwrap();
+        assert_eq!((last.p[0], last.p[1]), (100., 0.));
+    }
+}
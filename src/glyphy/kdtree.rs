@@ -0,0 +1,290 @@
+//! 自适应k-d树索引
+//!
+//! `recursion_near_arcs_of_cell`的细分结果本身就是一棵二分树（每次沿x或y对半切），
+//! `CellInfo::encode_blob_arc`会把它展开成最小格子的均匀网格，只有一处细节的字符也要为整张网格付出索引纹理。
+//! 这里直接把二分树编码成节点纹理，每个叶子只存一份。
+//!
+//! 节点纹理每个节点一个像素（RGBA8，小端u32）：
+//! - a = 0：叶子，rgb为宽索引（`IndexFormat::Wide`），与均匀网格的索引像素格式相同
+//! - a = 1 / 2：沿x / y对半切，rgb为第一个子节点（坐标较小的一半）的序号，第二个子节点紧随其后
+//!
+//! 数据纹理与`BlobArc::try_encode_tex`相同，按像素偏移依次存放去重后的端点数据。
+//! 解码见`decode::glyphy_sdf_from_kd_tex`。
+use std::{collections::BTreeMap, hash::Hasher};
+
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use super::{
+    blob::{arc_endpoint_encode, encode_index, quantize_x, quantize_y, EncodeError, IndexFormat, TexInfo},
+    geometry::{aabb::Aabb, arc::ArcEndpoint, point::PointExt},
+    sdf::glyphy_sdf_from_arc_list3,
+    util::GLYPHY_INFINITY,
+};
+use crate::{utils::CellInfo, Point};
+
+/// 叶子节点
+pub(crate) const KD_LEAF: u8 = 0;
+/// 沿x对半切
+pub(crate) const KD_SPLIT_X: u8 = 1;
+/// 沿y对半切
+pub(crate) const KD_SPLIT_Y: u8 = 2;
+
+/// k-d树索引的编码结果
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
+pub struct KdSdfInfo {
+    /// 纹理元信息，grid_w / grid_h为最小格子的数量，index_format固定为`IndexFormat::Wide`
+    pub tex_info: TexInfo,
+    /// 节点纹理，每个节点4字节
+    pub node_tex: Vec<u8>,
+    /// 数据纹理
+    pub data_tex: Vec<u8>,
+}
+
+impl KdSdfInfo {
+    /// 节点数量
+    pub fn node_count(&self) -> usize {
+        self.node_tex.len() / 4
+    }
+}
+
+// 细分得到的叶子，rect以最小格子为单位：[min_x, min_y, max_x, max_y]
+struct KdLeaf {
+    rect: [usize; 4],
+    num_points: usize,
+    offset: usize,
+    sdf: f32,
+    is_interval: bool,
+}
+
+impl CellInfo {
+    /// 把细分结果直接编码成k-d树索引，不展开成均匀网格
+    ///
+    /// # 返回
+    /// * `Result<KdSdfInfo, EncodeError>` - sdf和偏移超出宽索引的范围时返回`EncodeError::IndexOverflow`
+    pub fn encode_kd_tree(&self) -> Result<KdSdfInfo, EncodeError> {
+        let extents = &self.extents;
+        let glyph_width = extents.width();
+        let glyph_height = extents.height();
+        let grid_w = (glyph_width / self.min_width).round() as usize;
+        let grid_h = (glyph_height / self.min_height).round() as usize;
+        let cell_w = glyph_width / grid_w as f32;
+        let cell_h = glyph_height / grid_h as f32;
+
+        let mut data_tex = vec![];
+        // 按数据去重：键 -> 在数据纹理的偏移
+        let mut offsets = BTreeMap::new();
+        let mut leaves = Vec::with_capacity(self.info.len());
+        for (near_arcs, cell) in &self.info {
+            let endpoints = self.near_endpoints(near_arcs);
+
+            let mut hasher = pi_hash::DefaultHasher::default();
+            let mut key = Vec::with_capacity(endpoints.len() * 3);
+            for endpoint in &endpoints {
+                key.push(endpoint.p[0]);
+                key.push(endpoint.p[1]);
+                key.push(endpoint.d);
+            }
+            hasher.write(bytemuck::cast_slice(&key));
+
+            let offset = *offsets.entry(hasher.finish()).or_insert_with(|| {
+                let offset = data_tex.len() / 4;
+                write_endpoints(&mut data_tex, &endpoints, extents);
+                offset
+            });
+
+            let begin = cell.mins - extents.mins;
+            let end = cell.maxs - extents.mins;
+            let sdf = glyphy_sdf_from_arc_list3(near_arcs, cell.center(), &self.arcs).0;
+            let half_diagonal = (cell.width().powi(2) + cell.height().powi(2)).sqrt() * 0.5;
+            leaves.push(KdLeaf {
+                rect: [
+                    (begin.x / cell_w).round() as usize,
+                    (begin.y / cell_h).round() as usize,
+                    (end.x / cell_w).round() as usize,
+                    (end.y / cell_h).round() as usize,
+                ],
+                // 超过3个端点时读到全零像素为止
+                num_points: if endpoints.len() > 3 { 0 } else { endpoints.len() },
+                offset,
+                sdf,
+                is_interval: sdf.is_finite() && sdf.abs() <= half_diagonal,
+            });
+        }
+
+        let (mut min_sdf, mut max_sdf) = (0.0f32, 0.0f32);
+        if let Some(first) = leaves.iter().find(|leaf| leaf.is_interval) {
+            (min_sdf, max_sdf) = (first.sdf, first.sdf);
+        }
+        for leaf in leaves.iter().filter(|leaf| leaf.is_interval) {
+            min_sdf = min_sdf.min(leaf.sdf);
+            max_sdf = max_sdf.max(leaf.sdf);
+        }
+
+        let format = IndexFormat::Wide;
+        let max_offset = data_tex.len() / 4;
+        let level = format.sdf_level(max_offset);
+        let sdf_step = (max_sdf - min_sdf + 0.1) / level as f32;
+
+        let mut values = Vec::with_capacity(leaves.len());
+        for leaf in &leaves {
+            let [encode, _] = encode_index(
+                format,
+                leaf.is_interval,
+                leaf.num_points as f32,
+                leaf.offset as f32,
+                max_offset as f32,
+                leaf.sdf,
+                min_sdf,
+                sdf_step,
+            )?;
+            values.push(encode as u32);
+        }
+
+        let mut nodes = vec![0u32];
+        build_node(
+            &mut nodes,
+            0,
+            [0, 0, grid_w, grid_h],
+            (0..leaves.len()).collect(),
+            &leaves,
+            &values,
+        );
+
+        Ok(KdSdfInfo {
+            tex_info: TexInfo {
+                grid_w: grid_w as f32,
+                grid_h: grid_h as f32,
+                cell_size: cell_w,
+                max_offset,
+                min_sdf,
+                sdf_step,
+                index_format: format,
+                extents_min_x: extents.mins.x,
+                extents_min_y: extents.mins.y,
+                extents_max_x: extents.maxs.x,
+                extents_max_y: extents.maxs.y,
+                ..Default::default()
+            },
+            node_tex: nodes.iter().flat_map(|node| node.to_le_bytes()).collect(),
+            data_tex,
+        })
+    }
+
+    // 格子的近段圆弧转成端点，不相连处插入move_to
+    fn near_endpoints(&self, near_arcs: &Vec<usize>) -> Vec<ArcEndpoint> {
+        let mut endpoints = Vec::with_capacity(near_arcs.len() * 2);
+        let mut p1 = Point::new(0.0, 0.0);
+        for (i, index) in near_arcs.iter().enumerate() {
+            let arc = &self.arcs[*index];
+            if i == 0 || !p1.equals(&arc.p0) {
+                endpoints.push(ArcEndpoint::new(arc.p0.x, arc.p0.y, GLYPHY_INFINITY));
+            }
+            endpoints.push(ArcEndpoint::new(arc.p1.x, arc.p1.y, arc.d));
+            p1 = arc.p1;
+        }
+        endpoints
+    }
+}
+
+fn write_endpoints(data_tex: &mut Vec<u8>, endpoints: &[ArcEndpoint], extents: &Aabb) {
+    let glyph_width = extents.width();
+    let glyph_height = extents.height();
+    for endpoint in endpoints {
+        let qx = quantize_x(endpoint.p[0], extents, glyph_width);
+        let qy = quantize_y(endpoint.p[1], extents, glyph_height);
+        let rgba = arc_endpoint_encode(qx, qy, endpoint.d);
+        data_tex.extend(rgba.map(|v| v as u8));
+    }
+
+    if endpoints.len() > 3 {
+        // 补充一个全零像素代表结束
+        data_tex.extend([0; 4]);
+    } else if endpoints.is_empty() {
+        // 第一个像素总是作为起点读取，空数据需要两个全零像素
+        data_tex.extend([0; 8]);
+    }
+}
+
+// 按区域递归建树，区域和叶子一样以最小格子为单位
+fn build_node(
+    nodes: &mut Vec<u32>,
+    node: usize,
+    region: [usize; 4],
+    items: Vec<usize>,
+    leaves: &[KdLeaf],
+    values: &[u32],
+) {
+    if let [i] = items[..] {
+        if leaves[i].rect == region {
+            nodes[node] = ((KD_LEAF as u32) << 24) | values[i];
+            return;
+        }
+    }
+
+    let [x0, y0, x1, y1] = region;
+    let (mid_x, mid_y) = ((x0 + x1) / 2, (y0 + y1) / 2);
+    let can_x = x1 - x0 >= 2
+        && items
+            .iter()
+            .all(|&i| leaves[i].rect[2] <= mid_x || leaves[i].rect[0] >= mid_x);
+    let can_y = y1 - y0 >= 2
+        && items
+            .iter()
+            .all(|&i| leaves[i].rect[3] <= mid_y || leaves[i].rect[1] >= mid_y);
+    assert!(
+        !items.is_empty() && (can_x || can_y),
+        "cells are not a binary subdivision of the extents"
+    );
+
+    // 与细分时一样，宽大于高时优先竖切
+    let split_x = can_x && (!can_y || x1 - x0 > y1 - y0);
+    let (first_items, second_items): (Vec<usize>, Vec<usize>) = items.into_iter().partition(|&i| {
+        if split_x {
+            leaves[i].rect[2] <= mid_x
+        } else {
+            leaves[i].rect[3] <= mid_y
+        }
+    });
+    let (first_region, second_region, ty) = if split_x {
+        ([x0, y0, mid_x, y1], [mid_x, y0, x1, y1], KD_SPLIT_X)
+    } else {
+        ([x0, y0, x1, mid_y], [x0, mid_y, x1, y1], KD_SPLIT_Y)
+    };
+
+    let child = nodes.len();
+    nodes.push(0);
+    nodes.push(0);
+    nodes[node] = ((ty as u32) << 24) | child as u32;
+    build_node(nodes, child, first_region, first_items, leaves, values);
+    build_node(nodes, child + 1, second_region, second_items, leaves, values);
+}
+
+#[test]
+fn test() {
+    use super::decode::{glyphy_sdf_from_kd_tex, glyphy_sdf_from_tex};
+    use crate::shape::Circle;
+
+    let cell = Circle::new(50.0, 50.0, 40.0)
+        .unwrap()
+        .get_svg_info()
+        .compute_near_arcs(1.0);
+    let kd = cell.encode_kd_tree().unwrap();
+    let uniform = cell.encode_blob_arc().try_encode_tex().unwrap();
+    let (grid_w, grid_h) = (kd.tex_info.grid_w, kd.tex_info.grid_h);
+    assert_eq!((grid_w, grid_h), (uniform.tex_info.grid_w, uniform.tex_info.grid_h));
+    // 叶子数 = (节点数 + 1) / 2，不超过最小格子数
+    assert!(kd.node_count() < 2 * (grid_w * grid_h) as usize);
+
+    // 两种索引读到的是同一组圆弧
+    for j in 0..grid_h as usize {
+        for i in 0..grid_w as usize {
+            let p = Point::new(i as f32 + 0.5, j as f32 + 0.5);
+            let a = glyphy_sdf_from_kd_tex(&kd, p);
+            let b = glyphy_sdf_from_tex(&uniform, p);
+            assert!((a - b).abs() < 1e-3, "cell ({}, {}): {} != {}", i, j, a, b);
+        }
+    }
+}
@@ -1,7 +1,14 @@
 pub mod arc_bezier;
+pub mod atlas;
 pub mod blob;
+pub mod boolean;
+pub mod decode;
 pub mod geometry;
+pub mod kdtree;
+pub mod offset;
 pub mod outline;
 pub mod sdf;
+pub mod simplify;
+pub mod stats;
 pub mod util;
 pub mod vertex;
\ No newline at end of file
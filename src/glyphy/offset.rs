@@ -0,0 +1,197 @@
+//! 圆弧轮廓的偏移（外扩和内缩）
+//!
+//! 直线平移后仍是直线，圆弧偏移后是同心的圆弧（半径加减偏移距离，扫过角不变），
+//! 所以偏移的结果是精确的，不需要重新拟合。相邻两段在凸角处按连接方式补上圆弧、斜接或斜角，
+//! 凹角处先连回原来的顶点，再和布尔运算一样按环绕数去掉自相交产生的多余部分。
+use kurbo::{Point as KPoint, Vec2};
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use super::boolean::{
+    from_flat, select, signed_area, to_contours, to_endpoints, to_flat, tolerance, Edge,
+};
+use super::geometry::arc::ArcEndpoint;
+
+/// 斜接的长度（顶点到斜接点的距离）超过偏移距离的倍数时改用斜角，与SVG的`stroke-miterlimit`默认值相同
+pub const MITER_LIMIT: f32 = 4.0;
+
+/// 偏移时凸角的连接方式
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OffsetJoin {
+    /// 以顶点为圆心的圆弧
+    Round,
+    /// 两段的延长线相交成尖角，过长时改用斜角
+    Miter,
+    /// 直线连接两段的端点
+    Bevel,
+}
+
+/// 偏移圆弧端点表示的轮廓
+///
+/// 外轮廓和洞按非零规则判断内外，`distance`为正时填充部分变大，为负时变小；
+/// 内缩超过圆弧半径或者轮廓宽度的一半时，对应的部分会消失
+///
+/// # 参数
+/// * `endpoints` - 圆弧端点，`d`为`GLYPHY_INFINITY`的端点开始新的轮廓，没有闭合的轮廓按首尾相连处理
+/// * `distance` - 偏移距离，与端点坐标的单位相同
+/// * `join` - 凸角的连接方式
+///
+/// # 返回
+/// * `Vec<ArcEndpoint>` - 偏移后的圆弧端点，方向与原轮廓一致，每段圆弧的`|d|`不超过`GLYPHY_MAX_D`
+pub fn offset_contours(
+    endpoints: &[ArcEndpoint],
+    distance: f32,
+    join: OffsetJoin,
+) -> Vec<ArcEndpoint> {
+    let contours = to_contours(endpoints);
+    let all: Vec<Edge> = contours.iter().flatten().copied().collect();
+    if all.is_empty() {
+        return vec![];
+    }
+
+    // 逆时针时内部在左侧，向右偏移为外扩
+    let sign = if signed_area(&all) >= 0.0 { 1.0 } else { -1.0 };
+    let distance = distance as f64;
+    let delta = distance * sign;
+    let eps = tolerance(&all);
+
+    // 右侧的单位法向
+    let right = |t: Vec2| Vec2::new(t.y, -t.x) / t.hypot();
+    let mut edges = vec![];
+    let push_line = |edges: &mut Vec<Edge>, p0: KPoint, p1: KPoint| {
+        if (p1 - p0).hypot() >= eps {
+            edges.push(Edge::new(p0, p1, 0.0));
+        }
+    };
+
+    for contour in &contours {
+        for (i, e) in contour.iter().enumerate() {
+            let a0 = e.p0 + right(e.tangent(0.0)) * delta;
+            let a1 = e.p1 + right(e.tangent(1.0)) * delta;
+            let radius = if e.sweep > 0.0 {
+                e.radius + delta
+            } else {
+                e.radius - delta
+            };
+            if !e.is_line() && radius <= 0.0 {
+                // 半径减到0以下的圆弧翻到圆心另一侧，经过圆心连接，之后会被去掉
+                push_line(&mut edges, a0, e.center);
+                push_line(&mut edges, e.center, a1);
+            } else if (a1 - a0).hypot() >= eps {
+                edges.push(Edge::new(a0, a1, e.d));
+            }
+
+            // 和下一段在顶点 v 处连接
+            let next = &contour[(i + 1) % contour.len()];
+            let v = e.p1;
+            let t0 = e.tangent(1.0);
+            let t1 = next.tangent(0.0);
+            let b = next.p0 + right(t1) * delta;
+            if (b - a1).hypot() < eps {
+                continue;
+            }
+
+            let cross = t0.cross(t1);
+            if cross * delta <= 0.0 {
+                // 凹角：连回顶点，重叠的部分之后会被去掉
+                push_line(&mut edges, a1, v);
+                push_line(&mut edges, v, b);
+                continue;
+            }
+            match join {
+                OffsetJoin::Round => {
+                    let (u, w) = (a1 - v, b - v);
+                    let sweep = u.cross(w).atan2(u.dot(w));
+                    edges.push(Edge::new(a1, b, (sweep / 4.0).tan()));
+                }
+                OffsetJoin::Miter => {
+                    // 两段在端点处切线的交点
+                    let m = a1 + t0 * ((b - a1).cross(t1) / cross);
+                    if (m - v).hypot() <= MITER_LIMIT as f64 * distance.abs() {
+                        push_line(&mut edges, a1, m);
+                        push_line(&mut edges, m, b);
+                    } else {
+                        push_line(&mut edges, a1, b);
+                    }
+                }
+                OffsetJoin::Bevel => push_line(&mut edges, a1, b),
+            }
+        }
+    }
+
+    let (mut kept, eps) = select(&[edges], |w| w[0] as f64 * sign > 0.0);
+    if sign < 0.0 {
+        kept = kept.iter().map(Edge::reversed).collect();
+    }
+    to_endpoints(&kept, eps)
+}
+
+/// 通过 wasm 绑定偏移轮廓，端点按`[x, y, d, x, y, d, ...]`平铺，与`SvgInfo::new`的参数相同
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn offset_contours_of_wasm(endpoints: &[f32], distance: f32, join: OffsetJoin) -> Vec<f32> {
+    to_flat(&offset_contours(&from_flat(endpoints), distance, join))
+}
+
+#[test]
+fn test() {
+    use super::boolean::to_edges;
+    use super::util::{GLYPHY_INFINITY, GLYPHY_MAX_D};
+    use std::f64::consts::PI;
+
+    let area = |endpoints: &[ArcEndpoint]| signed_area(&to_edges(endpoints));
+
+    // 逆时针的正方形
+    let rect = vec![
+        ArcEndpoint::new(0.0, 0.0, GLYPHY_INFINITY),
+        ArcEndpoint::new(100.0, 0.0, 0.0),
+        ArcEndpoint::new(100.0, 100.0, 0.0),
+        ArcEndpoint::new(0.0, 100.0, 0.0),
+        ArcEndpoint::new(0.0, 0.0, 0.0),
+    ];
+    for (join, expected) in [
+        (OffsetJoin::Round, 14000.0 + PI * 100.0),
+        (OffsetJoin::Miter, 14400.0),
+        (OffsetJoin::Bevel, 14200.0),
+    ] {
+        let result = offset_contours(&rect, 10.0, join);
+        assert!((area(&result) - expected).abs() < 1e-2, "{:?}", join);
+        assert!(result.iter().all(|e| e.d == GLYPHY_INFINITY || e.d.abs() <= GLYPHY_MAX_D));
+    }
+    let result = offset_contours(&rect, -10.0, OffsetJoin::Round);
+    assert!((area(&result) - 6400.0).abs() < 1e-2);
+
+    // 顺时针的圆，偏移后仍是圆，方向不变
+    let circle = crate::shape::Circle::new(100.0, 100.0, 50.0)
+        .unwrap()
+        .get_arc_endpoints();
+    let result = offset_contours(&circle, 10.0, OffsetJoin::Round);
+    assert!((area(&result) + PI * 3600.0).abs() < 1e-1);
+    let result = offset_contours(&circle, -20.0, OffsetJoin::Miter);
+    assert!((area(&result) + PI * 900.0).abs() < 1e-1);
+    // 内缩超过半径时消失
+    assert!(offset_contours(&circle, -60.0, OffsetJoin::Round).is_empty());
+
+    // L形，凹角处的自相交被去掉；内缩时凹角变为凸角
+    let l = vec![
+        ArcEndpoint::new(0.0, 0.0, GLYPHY_INFINITY),
+        ArcEndpoint::new(100.0, 0.0, 0.0),
+        ArcEndpoint::new(100.0, 50.0, 0.0),
+        ArcEndpoint::new(50.0, 50.0, 0.0),
+        ArcEndpoint::new(50.0, 100.0, 0.0),
+        ArcEndpoint::new(0.0, 100.0, 0.0),
+        ArcEndpoint::new(0.0, 0.0, 0.0),
+    ];
+    let result = offset_contours(&l, -10.0, OffsetJoin::Bevel);
+    assert!((area(&result) - 3950.0).abs() < 1e-2);
+    let result = offset_contours(&l, 10.0, OffsetJoin::Miter);
+    assert!((area(&result) - 11900.0).abs() < 1e-2);
+
+    let flat = offset_contours_of_wasm(
+        &[0.0, 0.0, f32::INFINITY, 100.0, 0.0, 0.0, 100.0, 100.0, 0.0, 0.0, 100.0, 0.0, 0.0, 0.0, 0.0],
+        5.0,
+        OffsetJoin::Bevel,
+    );
+    assert_eq!(flat.len() % 3, 0);
+}
@@ -0,0 +1,163 @@
+//! 圆弧端点的化简
+//!
+//! 字体中大量短的共线线段、`Polygon`/`Polyline`的折线会产生很长的端点列表，
+//! 相邻的近似共线或共圆的线段和圆弧可以合并成一段，减少`CellInfo.arcs`和每个格子的圆弧数。
+//! 只删除轮廓中间的端点，轮廓的起点和终点不变，所以闭合和方向都保持不变。
+use super::geometry::arc::{arc_midpoint, Arc, ArcEndpoint};
+use super::util::{GLYPHY_INFINITY, GLYPHY_MAX_D};
+use crate::Point;
+
+/// 化简圆弧端点，合并误差在`tolerance`以内的相邻线段和圆弧
+///
+/// # 参数
+/// * `endpoints` - 圆弧端点，`d`为`GLYPHY_INFINITY`的端点开始新的轮廓
+/// * `tolerance` - 合并后的圆弧到原轮廓的最大距离，与端点坐标的单位相同
+///
+/// # 返回
+/// * `Vec<ArcEndpoint>` - 化简后的端点
+pub fn simplify_endpoints(endpoints: &[ArcEndpoint], tolerance: f32) -> Vec<ArcEndpoint> {
+    let mut result = Vec::with_capacity(endpoints.len());
+    // 正在合并的一段：起点，之后各段的 (终点, d)，和合并成的圆弧的 d
+    let mut start: Option<Point> = None;
+    let mut run: Vec<(Point, f32)> = vec![];
+    let mut merged_d = 0.0;
+
+    for endpoint in endpoints {
+        let p = Point::new(endpoint.p[0], endpoint.p[1]);
+        if endpoint.d == GLYPHY_INFINITY {
+            flush(&mut result, &mut run, merged_d);
+            result.push(endpoint.clone());
+            start = Some(p);
+            continue;
+        }
+
+        let run_start = match start {
+            Some(run_start) => run_start,
+            // 没有起点的端点原样保留
+            None => {
+                result.push(endpoint.clone());
+                continue;
+            }
+        };
+
+        run.push((p, endpoint.d));
+        if run.len() == 1 {
+            merged_d = endpoint.d;
+            continue;
+        }
+
+        match fit_arc(run_start, &run, tolerance) {
+            Some(d) => merged_d = d,
+            None => {
+                // 合并不了，输出之前的部分，从这一段重新开始
+                run.pop();
+                let last = run.last().unwrap().0;
+                flush(&mut result, &mut run, merged_d);
+                start = Some(last);
+                run.push((p, endpoint.d));
+                merged_d = endpoint.d;
+            }
+        }
+    }
+    flush(&mut result, &mut run, merged_d);
+
+    result
+}
+
+// 输出合并好的一段
+fn flush(result: &mut Vec<ArcEndpoint>, run: &mut Vec<(Point, f32)>, d: f32) {
+    if let Some(&(p, _)) = run.last() {
+        result.push(ArcEndpoint::new(p.x, p.y, d));
+    }
+    run.clear();
+}
+
+// 用一段圆弧拟合从 p0 开始的各段，圆弧过两端和中间的点，
+// 各段的终点和中点到圆弧的距离都在精度以内时返回圆弧的 d
+fn fit_arc(p0: Point, pieces: &[(Point, f32)], tolerance: f32) -> Option<f32> {
+    let p1 = pieces.last().unwrap().0;
+    // 首尾相接的一段不能用一段圆弧表示
+    if p0 == p1 {
+        return None;
+    }
+
+    let n = pieces.len();
+    let pm = if n % 2 == 0 {
+        pieces[n / 2 - 1].0
+    } else {
+        let prev = if n == 1 { p0 } else { pieces[n / 2 - 1].0 };
+        let (p, d) = pieces[n / 2];
+        arc_midpoint(prev, p, d)
+    };
+
+    // from_points 只设置了 d，重新构造圆弧以得到圆心和半径
+    let mut d = Arc::from_points(p0, p1, pm, false).d;
+    if !d.is_finite() || d.abs() > GLYPHY_MAX_D {
+        return None;
+    }
+    // 共线时保持为线段
+    if d.abs() < 1e-5 {
+        d = 0.0;
+    }
+    let arc = Arc::new(p0, p1, d);
+
+    let mut prev = p0;
+    for &(p, piece_d) in pieces {
+        let mid = arc_midpoint(prev, p, piece_d);
+        if arc.distance_to_point(p).abs() > tolerance
+            || arc.distance_to_point(mid).abs() > tolerance
+        {
+            return None;
+        }
+        prev = p;
+    }
+    Some(d)
+}
+
+#[test]
+fn test() {
+    // 带方向的面积，只用端点估计
+    fn area(endpoints: &[ArcEndpoint]) -> f32 {
+        let mut s = 0.0;
+        for i in 1..endpoints.len() {
+            let (a, b) = (endpoints[i - 1].p, endpoints[i].p);
+            s += a[0] * b[1] - b[0] * a[1];
+        }
+        s * 0.5
+    }
+
+    // 每条边分成10段的正方形
+    let mut square = vec![ArcEndpoint::new(0.0, 0.0, GLYPHY_INFINITY)];
+    let corners = [[0.0, 0.0], [100.0, 0.0], [100.0, 100.0], [0.0, 100.0], [0.0, 0.0]];
+    for i in 0..4 {
+        let (a, b) = (corners[i], corners[i + 1]);
+        for j in 1..=10 {
+            let t = j as f32 / 10.0;
+            square.push(ArcEndpoint::new(
+                a[0] + (b[0] - a[0]) * t,
+                a[1] + (b[1] - a[1]) * t,
+                0.0,
+            ));
+        }
+    }
+    let result = simplify_endpoints(&square, 0.01);
+    assert_eq!(result.len(), 5);
+    for (endpoint, corner) in result.iter().zip(corners.iter()) {
+        assert_eq!(endpoint.p, *corner);
+    }
+    assert!(result[1..].iter().all(|e| e.d == 0.0));
+
+    // 64边形逼近的圆，方向为顺时针
+    let mut circle = vec![ArcEndpoint::new(100.0, 0.0, GLYPHY_INFINITY)];
+    for i in 1..=64 {
+        let angle = -(i as f32) / 64.0 * std::f32::consts::TAU;
+        circle.push(ArcEndpoint::new(100.0 * angle.cos(), 100.0 * angle.sin(), 0.0));
+    }
+    let last = circle[64].p;
+    let result = simplify_endpoints(&circle, 0.5);
+    assert!(result.len() < circle.len() / 4);
+    assert_eq!(result[0].p, circle[0].p);
+    assert_eq!(result.last().unwrap().p, last);
+    assert!(area(&result) < 0.0);
+    assert!(result[1..].iter().all(|e| e.d.abs() <= GLYPHY_MAX_D));
+}
@@ -0,0 +1,146 @@
+//! 编码质量统计
+//!
+//! 调整`scale`和拟合精度时，用来观察细分和编码的结果：格子数、每格圆弧数的分布、
+//! shader中每个像素读取数据纹理的次数、去重效果、纹理大小、拟合误差和耗时。
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use super::blob::{BlobArc, EncodeError, IndexFormat, SdfInfo};
+use crate::utils::CellInfo;
+
+/// 细分和编码的质量统计
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EncodeStats {
+    /// 细分得到的格子数
+    pub cell_count: usize,
+    /// 按最小格子展开后的网格列数
+    pub grid_w: usize,
+    /// 按最小格子展开后的网格行数
+    pub grid_h: usize,
+    /// 每格圆弧数的直方图：下标为圆弧数，值为细分格子的数量
+    pub arcs_histogram: Vec<u32>,
+    /// 每个最小格子在shader中读取数据纹理的平均像素数
+    pub avg_fetch: f32,
+    /// 每个最小格子在shader中读取数据纹理的最大像素数
+    pub max_fetch: usize,
+    /// 去重后的数据份数
+    pub unique_data: usize,
+    /// 去重率：去重后的数据份数 / 细分得到的格子数，越小说明共享的数据越多
+    pub dedup_ratio: f32,
+    /// 数据纹理的字节数
+    pub data_tex_bytes: usize,
+    /// 索引纹理的字节数
+    pub index_tex_bytes: usize,
+    /// 索引纹理的编码格式
+    pub index_format: IndexFormat,
+    /// 圆弧拟合贝塞尔曲线的最大误差
+    pub max_arc_error: f32,
+    /// 细分格子、计算近段圆弧的耗时（毫秒）
+    pub near_arcs_ms: f64,
+    /// 编码BlobArc和纹理的耗时（毫秒）
+    pub encode_ms: f64,
+}
+
+impl EncodeStats {
+    /// 统计细分和编码的结果，拟合误差和耗时为0，由调用者填写
+    ///
+    /// # 参数
+    /// * `cell` - 细分结果
+    /// * `blob` - `cell.encode_blob_arc()`的结果
+    /// * `tex` - `blob`编码得到的纹理
+    pub fn new(cell: &CellInfo, blob: &BlobArc, tex: &SdfInfo) -> Self {
+        let mut arcs_histogram = vec![];
+        for (near_arcs, _) in &cell.info {
+            if arcs_histogram.len() <= near_arcs.len() {
+                arcs_histogram.resize(near_arcs.len() + 1, 0);
+            }
+            arcs_histogram[near_arcs.len()] += 1;
+        }
+
+        let (grid_w, grid_h) = blob.grid_size();
+        let cell_count = cell.info.len();
+        let unique_data = blob.data_tex_map.len();
+        Self {
+            cell_count,
+            grid_w: grid_w as usize,
+            grid_h: grid_h as usize,
+            arcs_histogram,
+            avg_fetch: blob.avg_fetch_achieved,
+            max_fetch: blob.fetch_counts().max().unwrap_or(0),
+            unique_data,
+            dedup_ratio: if cell_count == 0 {
+                0.0
+            } else {
+                unique_data as f32 / cell_count as f32
+            },
+            data_tex_bytes: tex.data_tex.len(),
+            index_tex_bytes: tex.index_tex.len(),
+            index_format: tex.tex_info.index_format,
+            max_arc_error: 0.0,
+            near_arcs_ms: 0.0,
+            encode_ms: 0.0,
+        }
+    }
+}
+
+/// 细分并编码成纹理，同时统计编码质量
+///
+/// # 参数
+/// * `compute_near_arcs` - 细分格子、计算近段圆弧
+/// * `max_arc_error` - 得到端点时圆弧拟合贝塞尔曲线的最大误差
+pub(crate) fn encode_with_stats(
+    compute_near_arcs: impl FnOnce() -> CellInfo,
+    max_arc_error: f32,
+) -> Result<(SdfInfo, EncodeStats), EncodeError> {
+    let (cell, near_arcs_ms) = elapsed_ms(compute_near_arcs);
+    let (r, encode_ms) = elapsed_ms(|| {
+        let blob = cell.encode_blob_arc();
+        blob.try_encode_tex().map(|tex| (blob, tex))
+    });
+    let (blob, tex) = r?;
+
+    let mut stats = EncodeStats::new(&cell, &blob, &tex);
+    stats.max_arc_error = max_arc_error;
+    stats.near_arcs_ms = near_arcs_ms;
+    stats.encode_ms = encode_ms;
+    Ok((tex, stats))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn elapsed_ms<R>(f: impl FnOnce() -> R) -> (R, f64) {
+    let start = std::time::Instant::now();
+    let r = f();
+    (r, start.elapsed().as_secs_f64() * 1000.0)
+}
+
+// wasm中没有Instant，使用performance.now()
+#[cfg(target_arch = "wasm32")]
+fn elapsed_ms<R>(f: impl FnOnce() -> R) -> (R, f64) {
+    let performance = web_sys::window().and_then(|window| window.performance());
+    let now = || performance.as_ref().map_or(0.0, |performance| performance.now());
+    let start = now();
+    let r = f();
+    (r, now() - start)
+}
+
+#[test]
+fn test() {
+    use crate::shape::Circle;
+
+    let info = Circle::new(50.0, 50.0, 40.0).unwrap().get_svg_info();
+    let (tex, stats) = info.encode_with_stats(1.0).unwrap();
+
+    assert_eq!(stats.cell_count, stats.arcs_histogram.iter().sum::<u32>() as usize);
+    assert_eq!(stats.data_tex_bytes, tex.data_tex.len());
+    assert_eq!(
+        stats.index_tex_bytes,
+        stats.grid_w * stats.grid_h * stats.index_format.bytes()
+    );
+    assert!(stats.unique_data <= stats.cell_count);
+    assert!(stats.avg_fetch >= 2.0 && stats.avg_fetch <= stats.max_fetch as f32);
+
+    let blob = info.compute_near_arcs(1.0).encode_blob_arc();
+    assert_eq!(blob.avg_fetch_achieved, stats.avg_fetch);
+}
@@ -5,9 +5,16 @@ extern crate lazy_static;
 
 use std::io::Read;
 
+pub mod atlas;
+#[cfg(all(feature = "bake", not(target_arch = "wasm32")))]
+pub mod bake;
 pub mod blur;
+pub mod dynamic_atlas;
+pub mod export;
 pub mod font;
 pub mod glyphy;
+pub mod mipmap;
+pub mod raster;
 pub mod shape;
 pub mod svg;
 pub mod utils;
@@ -0,0 +1,181 @@
+//! SDF纹理的mipmap生成
+//!
+//! 直接对alpha做平均会让边缘（127）在缩小时漂移，且每层的距离范围与`pxrange`不一致。
+//! 这里先把alpha还原为以像素为单位的距离，在距离空间做盒式滤波，再按下一层的像素大小重新编码，
+//! 使得每一层纹理中 alpha 与距离的对应关系都和第0层相同（同样的`inner_range`、`outer_range`）。
+//!
+//! 注意：超出范围的距离在上一层已被截断，下采样后远离边缘的区域会偏小；外发光纹理的编码方式不同，不适用。
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::utils::SdfInfo2;
+
+/// 边缘对应的alpha值，与`compute_sdf2`一致
+const EDGE: f32 = 127.0;
+
+/// mipmap的一层
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SdfMipLevel {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// 将alpha还原为距离（像素，内部为负）
+fn decode_distance(a: u8, inner_range: f32, outer_range: f32) -> f32 {
+    let t = 1.0 - a as f32 / EDGE;
+    if t > 0.0 {
+        t * outer_range
+    } else {
+        t * inner_range
+    }
+}
+
+/// 将距离（像素，内部为负）编码为alpha
+fn encode_distance(d: f32, inner_range: f32, outer_range: f32) -> u8 {
+    let t = if d > 0.0 { d / outer_range } else { d / inner_range };
+    ((1.0 - t) * EDGE).round().clamp(0.0, 255.0) as u8
+}
+
+/// 在距离空间将一层纹理缩小为下一层
+///
+/// # 参数
+/// * `src` - 上一层的纹理数据
+/// * `width`、`height` - 上一层的宽高
+/// * `inner_range`、`outer_range` - 内外的距离范围（像素）
+///
+/// # 返回
+/// * `SdfMipLevel` - 宽高各减半（最小为1）的下一层
+pub fn downsample_sdf(
+    src: &[u8],
+    width: u32,
+    height: u32,
+    inner_range: f32,
+    outer_range: f32,
+) -> SdfMipLevel {
+    let (w, h) = (width as usize, height as usize);
+    let nw = (w / 2).max(1);
+    let nh = (h / 2).max(1);
+    // 新一层一个像素对应上一层的像素数，距离按此缩小
+    let scale = (w as f32 / nw as f32 + h as f32 / nh as f32) * 0.5;
+
+    let mut data = vec![0; nw * nh];
+    for y in 0..nh {
+        // 奇数尺寸时，最后一个像素覆盖剩余的3行/列
+        let y0 = y * h / nh;
+        let y1 = (y + 1) * h / nh;
+        for x in 0..nw {
+            let x0 = x * w / nw;
+            let x1 = (x + 1) * w / nw;
+
+            let mut sum = 0.0;
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    sum += decode_distance(src[sy * w + sx], inner_range, outer_range);
+                }
+            }
+            let d = sum / ((x1 - x0) * (y1 - y0)) as f32;
+            data[y * nw + x] = encode_distance(d / scale, inner_range, outer_range);
+        }
+    }
+
+    SdfMipLevel {
+        width: nw as u32,
+        height: nh as u32,
+        data,
+    }
+}
+
+/// 生成完整的mipmap链
+///
+/// # 参数
+/// * `tex` - 第0层纹理数据
+/// * `width`、`height` - 第0层的宽高
+/// * `inner_range`、`outer_range` - 内外的距离范围（像素），对称时都传`pxrange`
+/// * `max_levels` - 最多生成的层数（包含第0层），0表示一直生成到1x1
+///
+/// # 返回
+/// * `Vec<SdfMipLevel>` - 第一个元素为第0层的拷贝
+pub fn generate_sdf_mips(
+    tex: &[u8],
+    width: u32,
+    height: u32,
+    inner_range: f32,
+    outer_range: f32,
+    max_levels: usize,
+) -> Vec<SdfMipLevel> {
+    assert_eq!(tex.len(), (width * height) as usize);
+    let mut levels = vec![SdfMipLevel {
+        width,
+        height,
+        data: tex.to_vec(),
+    }];
+    loop {
+        let last = levels.last().unwrap();
+        if (last.width == 1 && last.height == 1)
+            || (max_levels > 0 && levels.len() >= max_levels)
+        {
+            break;
+        }
+        let next = downsample_sdf(
+            &last.data,
+            last.width,
+            last.height,
+            inner_range,
+            outer_range,
+        );
+        levels.push(next);
+    }
+    levels
+}
+
+impl SdfInfo2 {
+    /// 为SDF纹理生成mipmap链，距离范围取自`inner_range`、`outer_range`
+    pub fn generate_mips(&self, max_levels: usize) -> Vec<SdfMipLevel> {
+        generate_sdf_mips(
+            &self.sdf_tex,
+            self.tex_width,
+            self.tex_height,
+            self.inner_range,
+            self.outer_range,
+            max_levels,
+        )
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl SdfInfo2 {
+    /// 通过 wasm 绑定生成mipmap链
+    ///
+    /// # 参数
+    /// * `info` - 序列化后的SdfInfo2
+    /// * `max_levels` - 最多生成的层数（包含第0层），0表示一直生成到1x1
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`Vec<SdfMipLevel>`
+    pub fn generate_mips_of_wasm(info: &[u8], max_levels: usize) -> Vec<u8> {
+        let info: SdfInfo2 = bitcode::deserialize(info).unwrap();
+        bitcode::serialize(&info.generate_mips(max_levels)).unwrap()
+    }
+}
+
+#[test]
+fn test() {
+    // 一条竖直边缘：左侧内部，右侧外部，距离按像素递增
+    let (w, h, range) = (8u32, 8u32, 4.0);
+    let mut tex = vec![0; 64];
+    for y in 0..h as usize {
+        for x in 0..w as usize {
+            let d = x as f32 + 0.5 - 4.0;
+            tex[y * 8 + x] = encode_distance(d, range, range);
+        }
+    }
+    let mips = generate_sdf_mips(&tex, w, h, range, range, 0);
+    assert_eq!(mips.len(), 4);
+    assert_eq!((mips[3].width, mips[3].height), (1, 1));
+    // 边缘仍在中间，左右两侧关于127对称
+    let l1 = &mips[1].data;
+    assert_eq!(l1[1] as i32 - 127, 127 - l1[2] as i32);
+}
@@ -0,0 +1,308 @@
+//! 圆弧轮廓的抗锯齿覆盖率光栅化
+//!
+//! 直接在`ArcEndpoint`轮廓上计算每个像素被覆盖的面积：直线和圆弧都按像素边界切开后精确积分，没有展开误差。
+//! 用于极小字号时代替SDF，以及作为测试中的参考结果。
+use crate::{
+    glyphy::{
+        geometry::{aabb::Aabb, arc::{Arc, ArcEndpoint}},
+        util::GLYPHY_INFINITY,
+    },
+    shape::SvgInfo,
+    utils::{OutlineInfo, SCALE},
+    Point,
+};
+
+/// 面积累积缓冲，按行累积每条边对像素覆盖面积的贡献，最后前缀求和得到覆盖率
+struct Accumulator {
+    w: usize,
+    h: usize,
+    a: Vec<f32>,
+}
+
+impl Accumulator {
+    fn new(w: usize, h: usize) -> Self {
+        Self {
+            w,
+            h,
+            // 末尾多留几个，边界上的贡献会写到下一个位置
+            a: vec![0.0; w * h + 4],
+        }
+    }
+
+    fn draw_line(&mut self, p0: Point, p1: Point) {
+        // 落在纹理外的部分投影到边界上，对面积的贡献不变
+        let (w, h) = (self.w as f32, self.h as f32);
+        let p0 = Point::new(p0.x.clamp(0.0, w), p0.y.clamp(0.0, h));
+        let p1 = Point::new(p1.x.clamp(0.0, w), p1.y.clamp(0.0, h));
+        if (p0.y - p1.y).abs() <= f32::EPSILON {
+            return;
+        }
+        let (dir, p0, p1) = if p0.y < p1.y {
+            (1.0, p0, p1)
+        } else {
+            (-1.0, p1, p0)
+        };
+        let dxdy = (p1.x - p0.x) / (p1.y - p0.y);
+        let mut x = p0.x;
+        let y0 = p0.y as usize;
+        let y1 = (p1.y.ceil() as usize).min(self.h);
+        for y in y0..y1 {
+            let linestart = y * self.w;
+            let dy = ((y + 1) as f32).min(p1.y) - (y as f32).max(p0.y);
+            let xnext = x + dxdy * dy;
+            let d = dy * dir;
+            let (x0, x1) = if x < xnext { (x, xnext) } else { (xnext, x) };
+            let x0floor = x0.floor();
+            let x0i = x0floor as usize;
+            let x1ceil = x1.ceil();
+            let x1i = x1ceil as usize;
+            if x1i <= x0i + 1 {
+                // 在同一个像素内
+                let xmf = 0.5 * (x + xnext) - x0floor;
+                self.a[linestart + x0i] += d - d * xmf;
+                self.a[linestart + x0i + 1] += d * xmf;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0f = x0 - x0floor;
+                let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+                let x1f = x1 - x1ceil + 1.0;
+                let am = 0.5 * s * x1f * x1f;
+                self.a[linestart + x0i] += d * a0;
+                if x1i == x0i + 2 {
+                    self.a[linestart + x0i + 1] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - x0f);
+                    self.a[linestart + x0i + 1] += d * (a1 - a0);
+                    for xi in x0i + 2..x1i - 1 {
+                        self.a[linestart + xi] += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    self.a[linestart + x1i - 1] += d * (1.0 - a2 - am);
+                }
+                self.a[linestart + x1i] += d * am;
+            }
+            x = xnext;
+        }
+    }
+
+    /// 累积圆弧的贡献
+    ///
+    /// 圆弧在像素的行列边界处切开，每一小段都落在一个像素内，对该像素的贡献是
+    /// `∫(x0 + 1 - x) dy`，由圆的参数方程直接积分得到，右侧的像素得到完整的`dy`
+    fn draw_arc(&mut self, p0: Point, p1: Point, d: f32) {
+        if d.abs() < 1e-5 {
+            self.draw_line(p0, p1);
+            return;
+        }
+        let arc = Arc::new(p0, p1, d);
+        let (cx, cy) = (arc.center().x as f64, arc.center().y as f64);
+        let r = arc.radius as f64;
+        let start = ((p0.y as f64) - cy).atan2((p0.x as f64) - cx);
+        let sweep = 4.0 * (d as f64).atan();
+
+        // 与整数网格线的交点，用圆弧上的参数（0到1）表示
+        let mut ts = vec![0.0, 1.0];
+        let mut cross = |k: f64, c: f64, offset: f64| {
+            let cos = (k - c) / r;
+            if cos.abs() > 1.0 {
+                return;
+            }
+            let base = cos.acos();
+            for angle in [base + offset, -base + offset] {
+                let u = ((angle - start) * sweep.signum()).rem_euclid(std::f64::consts::TAU);
+                let t = u / sweep.abs();
+                if t > 0.0 && t < 1.0 {
+                    ts.push(t);
+                }
+            }
+        };
+        for k in ((cx - r).ceil().max(0.0) as usize)..=((cx + r).floor().min(self.w as f64) as usize) {
+            cross(k as f64, cx, 0.0);
+        }
+        // sin(t) = k，即 cos(t - PI/2) = k
+        for k in ((cy - r).ceil().max(0.0) as usize)..=((cy + r).floor().min(self.h as f64) as usize) {
+            cross(k as f64, cy, std::f64::consts::FRAC_PI_2);
+        }
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // ∫x dy 的原函数
+        let integral = |a: f64| cx * r * a.sin() + r * r * (0.5 * a + 0.25 * (2.0 * a).sin());
+        let (w, h) = (self.w as f64, self.h as f64);
+        for t in ts.windows(2) {
+            if t[1] - t[0] <= f64::EPSILON {
+                continue;
+            }
+            let (a0, a1) = (start + sweep * t[0], start + sweep * t[1]);
+            let am = 0.5 * (a0 + a1);
+            let (mx, my) = (cx + r * am.cos(), cy + r * am.sin());
+            // 纹理上下方的部分投影到边界上没有面积
+            if my < 0.0 || my >= h {
+                continue;
+            }
+            let dy = r * (a1.sin() - a0.sin());
+            let linestart = my as usize * self.w;
+            // 左右两侧的部分投影到边界上
+            let (xi, c0) = if mx < 0.0 {
+                (0, dy)
+            } else if mx >= w {
+                (self.w, dy)
+            } else {
+                let xi = mx.floor();
+                (xi as usize, (xi + 1.0) * dy - (integral(a1) - integral(a0)))
+            };
+            self.a[linestart + xi] += c0 as f32;
+            self.a[linestart + xi + 1] += (dy - c0) as f32;
+        }
+    }
+
+    fn into_alpha(self) -> Vec<u8> {
+        let mut acc = 0.0;
+        let mut alpha = Vec::with_capacity(self.w * self.h);
+        for i in 0..self.w * self.h {
+            acc += self.a[i];
+            alpha.push((acc.abs().min(1.0) * 255.0).round() as u8);
+        }
+        alpha
+    }
+}
+
+/// 光栅化圆弧轮廓，得到抗锯齿的覆盖率位图
+///
+/// 未闭合的轮廓会自动连回起点，重叠部分按覆盖面积相加后截断到1。
+///
+/// # 参数
+/// * `endpoints` - 圆弧端点，d为无穷大时表示新轮廓的起点
+/// * `bbox` - 需要光栅化的范围
+/// * `scale` - 每单位对应的像素数
+/// * `flip_y` - 是否翻转y轴（字体坐标y轴向上，位图第0行为顶部）
+///
+/// # 返回
+/// * `(Vec<u8>, u32, u32)` - 覆盖率位图、宽、高
+pub fn rasterize_endpoints(
+    endpoints: &[ArcEndpoint],
+    bbox: &Aabb,
+    scale: f32,
+    flip_y: bool,
+) -> (Vec<u8>, u32, u32) {
+    let w = ((bbox.width() * scale).ceil() as usize).max(1);
+    let h = ((bbox.height() * scale).ceil() as usize).max(1);
+    let mut acc = Accumulator::new(w, h);
+
+    let to_pixel = |x: f32, y: f32| {
+        if flip_y {
+            Point::new((x - bbox.mins.x) * scale, (bbox.maxs.y - y) * scale)
+        } else {
+            Point::new((x - bbox.mins.x) * scale, (y - bbox.mins.y) * scale)
+        }
+    };
+
+    let mut start = None;
+    let mut p0 = Point::new(0.0, 0.0);
+    for endpoint in endpoints {
+        let p = to_pixel(endpoint.p[0], endpoint.p[1]);
+        if endpoint.d == GLYPHY_INFINITY {
+            if let Some(s) = start.take() {
+                acc.draw_line(p0, s);
+            }
+            start = Some(p);
+            p0 = p;
+            continue;
+        }
+        if start.is_none() {
+            start = Some(p0);
+        }
+        // 翻转y轴后圆弧方向相反
+        let d = if flip_y { -endpoint.d } else { endpoint.d };
+        acc.draw_arc(p0, p, d);
+        p0 = p;
+    }
+    if let Some(s) = start {
+        acc.draw_line(p0, s);
+    }
+
+    (acc.into_alpha(), w as u32, h as u32)
+}
+
+impl OutlineInfo {
+    /// 按字号光栅化字符，得到覆盖率位图，位图范围为`extents`
+    ///
+    /// # 参数
+    /// * `font_size` - 字号（像素）
+    ///
+    /// # 返回
+    /// * `(Vec<u8>, u32, u32)` - 覆盖率位图、宽、高
+    pub fn rasterize(&self, font_size: f32) -> (Vec<u8>, u32, u32) {
+        let bbox = Aabb::new(
+            Point::new(self.extents[0], self.extents[1]),
+            Point::new(self.extents[2], self.extents[3]),
+        );
+        // 轮廓已缩放到每em为SCALE个单位
+        rasterize_endpoints(
+            &self.endpoints,
+            &bbox,
+            font_size / SCALE,
+            true,
+        )
+    }
+}
+
+impl SvgInfo {
+    /// 光栅化svg图形，得到覆盖率位图，位图范围为`binding_box`
+    ///
+    /// # 参数
+    /// * `scale` - 每单位对应的像素数
+    ///
+    /// # 返回
+    /// * `(Vec<u8>, u32, u32)` - 覆盖率位图、宽、高
+    pub fn rasterize(&self, scale: f32) -> (Vec<u8>, u32, u32) {
+        let bbox = Aabb::new(
+            Point::new(self.binding_box[0], self.binding_box[1]),
+            Point::new(self.binding_box[2], self.binding_box[3]),
+        );
+        rasterize_endpoints(
+            &self.arc_endpoints,
+            &bbox,
+            scale,
+            false,
+        )
+    }
+}
+
+#[test]
+fn test() {
+    let coverage = |alpha: &[u8]| alpha.iter().map(|a| *a as f32 / 255.0).sum::<f32>();
+
+    // 半径为8的圆，覆盖面积为 PI * r * r，误差只来自u8的量化
+    let d = (std::f32::consts::PI / 4.0).tan();
+    let endpoints = vec![
+        ArcEndpoint::new(16.0, 8.0, GLYPHY_INFINITY),
+        ArcEndpoint::new(0.0, 8.0, d),
+        ArcEndpoint::new(16.0, 8.0, d),
+    ];
+    let bbox = Aabb::new(Point::new(0.0, 0.0), Point::new(16.0, 16.0));
+    let (alpha, w, h) = rasterize_endpoints(&endpoints, &bbox, 1.0, false);
+    assert_eq!((w, h), (16, 16));
+    assert!((coverage(&alpha) - std::f32::consts::PI * 64.0).abs() < 0.1);
+    assert_eq!(alpha[8 * 16 + 8], 255);
+    assert_eq!(alpha[0], 0);
+    // 圆周经过的像素，覆盖率与按列数值积分的面积一致
+    let exact = |x0: f32, y0: f32| {
+        let n = 2000;
+        (0..n)
+            .map(|i| {
+                let x = x0 + (i as f32 + 0.5) / n as f32;
+                let half = (64.0 - (x - 8.0) * (x - 8.0)).max(0.0).sqrt();
+                ((8.0 + half).min(y0 + 1.0) - (8.0 - half).max(y0)).max(0.0) / n as f32
+            })
+            .sum::<f32>()
+    };
+    for (x, y) in [(2, 2), (1, 5), (13, 3), (8, 0)] {
+        let a = alpha[y * 16 + x] as f32 / 255.0;
+        assert!((a - exact(x as f32, y as f32)).abs() <= 0.5 / 255.0 + 1e-3, "{} {}", x, y);
+    }
+
+    // 伸出纹理的圆，只统计纹理内的部分：半圆
+    let bbox = Aabb::new(Point::new(8.0, 0.0), Point::new(24.0, 16.0));
+    let (alpha, _, _) = rasterize_endpoints(&endpoints, &bbox, 1.0, false);
+    assert!((coverage(&alpha) - std::f32::consts::PI * 32.0).abs() < 0.1);
+}
@@ -12,15 +12,22 @@ use parry2d::{
 use serde::{Deserialize, Serialize};
 // use usvg::tiny_skia_path::PathSegment;
 
-use crate::glyphy::blob::{recursion_near_arcs_of_cell, SdfInfo};
+use crate::export::outline::{endpoints_to_bez_path, endpoints_to_svg, SvgExportOptions};
+use crate::glyphy::blob::{
+    recursion_near_arcs_of_cell, CellBudget, CellBudgetError, DataFormat, EncodeError, SdfInfo,
+};
+use crate::glyphy::kdtree::KdSdfInfo;
+use crate::glyphy::simplify::simplify_endpoints;
+use crate::glyphy::stats::{encode_with_stats, EncodeStats};
 use crate::glyphy::geometry::arc::{Arc, ID};
+use crate::glyphy::geometry::arcs::GlyphyArcAccumulator;
 use crate::glyphy::geometry::segment::{PPoint, PSegment};
 use crate::glyphy::util::GLYPHY_INFINITY;
-use crate::utils::{compute_cell_range, CellInfo, LayoutInfo, OutlineSinkExt, SdfInfo2, TexInfo2};
+use crate::utils::{compute_cell_range, compute_cell_range_rect, CellInfo, LayoutInfo, OutlineSinkExt, SdfInfo2, Stroke, StrokeAlign, TexInfo2};
 use crate::Vector2;
 use crate::{
     glyphy::geometry::aabb::Aabb,
-    utils::{compute_layout, Attribute},
+    utils::{compute_layout, compute_layout_rect, encode_sdf_rect, Attribute},
 };
 use crate::{
     glyphy::{
@@ -39,6 +46,10 @@ use std::{
     mem::transmute,
 };
 pub const FARWAY: f32 = 20.0;
+/// 椭圆默认的圆弧拟合精度
+pub const ELLIPSE_TOLERANCE: f32 = 0.1;
+/// 路径默认的圆弧拟合精度
+pub const PATH_TOLERANCE: f32 = 0.01;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
@@ -228,6 +239,8 @@ impl Circle {
             is_reverse: None,
             hash: self.get_hash(),
             tex_size,
+            max_error: 0.0,
+            fit_failed: false,
         }
     }
 
@@ -243,6 +256,8 @@ impl Circle {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -376,6 +391,8 @@ impl Rect {
             is_reverse: None,
             hash: self.get_hash(),
             tex_size,
+            max_error: 0.0,
+            fit_failed: false,
         }
     }
 
@@ -389,6 +406,8 @@ impl Rect {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -538,6 +557,8 @@ impl Segment {
             is_reverse: None,
             hash: self.get_hash(),
             tex_size,
+            max_error: 0.0,
+            fit_failed: false,
         }
     }
 
@@ -552,6 +573,8 @@ impl Segment {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -579,6 +602,7 @@ pub struct Ellipse {
     cy: f32,                         // 椭圆的中心y坐标。
     rx: f32,                         // 椭圆的长半轴长度，控制x方向半径。
     ry: f32,                         // 椭圆的短半轴长度，控制y方向半径。
+    tolerance: f32,                  // 圆弧拟合贝塞尔曲线的精度。
     pub(crate) attribute: Attribute, // 绘制属性，定义椭圆的外观和行为特性。
 }
 
@@ -595,6 +619,18 @@ impl Ellipse {
     /// # 返回值
     /// 一个新的椭圆实例。
     pub fn new(cx: f32, cy: f32, rx: f32, ry: f32) -> Self {
+        Self::new_with_tolerance(cx, cy, rx, ry, ELLIPSE_TOLERANCE)
+    }
+
+    /// 创建一个新的椭圆实例，并指定圆弧拟合的精度。
+    ///
+    /// # 参数
+    /// - `cx`, `cy`, `rx`, `ry`: 同`new`。
+    /// - `tolerance`: 圆弧拟合贝塞尔曲线的精度，值越小越精确，圆弧也越多，默认为`ELLIPSE_TOLERANCE`。
+    ///
+    /// # 返回值
+    /// 一个新的椭圆实例。
+    pub fn new_with_tolerance(cx: f32, cy: f32, rx: f32, ry: f32, tolerance: f32) -> Self {
         let mut attribute = Attribute::default();
         attribute.start = Point::new(cx - rx, cy);
         attribute.is_close = true;
@@ -603,6 +639,7 @@ impl Ellipse {
             cy,
             rx,
             ry,
+            tolerance,
             attribute,
         }
     }
@@ -611,9 +648,15 @@ impl Ellipse {
     /// 这些端点用于绘制椭圆的边界，包括起始点和终点。
     /// returned一个包含这些端点的向量。
     pub fn get_arc_endpoints(&self) -> Vec<ArcEndpoint> {
+        self.fit_arc_endpoints().result
+    }
+
+    // 圆弧拟合椭圆的贝塞尔曲线，返回的累加器中还有拟合的最大误差
+    fn fit_arc_endpoints(&self) -> GlyphyArcAccumulator {
         let center = kurbo::Point::new(self.cx as f64, self.cy as f64);
         let e = kurbo::Ellipse::new(center, (self.rx as f64, self.ry as f64), 0.0);
-        let path = e.into_path(0.1);
+        // 误差是相对展开后的贝塞尔曲线计算的，展开的精度要比拟合精度高
+        let path = e.into_path((self.tolerance as f64 * 0.25).min(0.1));
 
         let mut verbs = Vec::with_capacity(path.elements().len());
         let mut points = Vec::with_capacity(path.elements().len() * 2);
@@ -647,7 +690,7 @@ impl Ellipse {
 
         let mut sink = GlyphVisitor::new(1.0);
         // 圆弧拟合贝塞尔曲线的精度，值越小越精确
-        sink.accumulate.tolerance = 0.1;
+        sink.accumulate.set_tolerance(self.tolerance);
         if e.area() > 0.0 {
             let temp = verbs[0];
             let len = verbs.len();
@@ -658,15 +701,15 @@ impl Ellipse {
             compute_outline(points.iter(), verbs.iter(), &mut sink, false)
         }
 
-        sink.accumulate.result
+        sink.accumulate
     }
 
     /// 获取椭圆的哈希值。
     ///
-    /// 该哈希值用于缓存和快速比较，基于椭圆的长轴和短轴长度的比例计算。
+    /// 该哈希值用于缓存和快速比较，基于椭圆的长轴和短轴长度的比例和拟合精度计算。
     pub fn get_hash(&self) -> u64 {
         let mut hasher = pi_hash::DefaultHasher::default();
-        hasher.write(bytemuck::cast_slice(&[self.rx / self.ry, 4.0]));
+        hasher.write(bytemuck::cast_slice(&[self.rx / self.ry, 4.0, self.tolerance]));
         hasher.finish()
     }
 
@@ -689,6 +732,7 @@ impl Ellipse {
             .max(binding_box.maxs.y - binding_box.mins.y)
             .ceil();
         let tex_size = if size < 64.0 { 32.0 } else { size * 0.5 };
+        let accumulate = self.fit_arc_endpoints();
 
         SvgInfo {
             binding_box: vec![
@@ -697,11 +741,13 @@ impl Ellipse {
                 binding_box.maxs.x,
                 binding_box.maxs.y,
             ],
-            arc_endpoints: self.get_arc_endpoints(),
             is_area: self.is_area(),
             is_reverse: None,
             hash: self.get_hash(),
             tex_size,
+            max_error: accumulate.max_error(),
+            fit_failed: !accumulate.success(),
+            arc_endpoints: accumulate.result,
         }
     }
 
@@ -717,6 +763,8 @@ impl Ellipse {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -841,6 +889,8 @@ impl Polygon {
             is_reverse: None,
             hash: self.get_hash(),
             tex_size,
+            max_error: 0.0,
+            fit_failed: false,
         }
     }
 
@@ -856,6 +906,8 @@ impl Polygon {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -1004,7 +1056,9 @@ impl Polyline {
             is_area: self.is_area(),
             is_reverse: None,
             hash: self.get_hash(),
-            tex_size
+            tex_size,
+            max_error: 0.0,
+            fit_failed: false,
         }
     }
 
@@ -1018,6 +1072,8 @@ impl Polyline {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -1047,6 +1103,8 @@ pub struct Path {
     pub(crate) attribute: Attribute,
     /// 标记路径是否反向绘制。
     is_reverse: bool,
+    /// 圆弧拟合贝塞尔曲线的精度。
+    tolerance: f32,
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
@@ -1078,6 +1136,25 @@ impl Path {
     /// # 返回值
     /// 一个新的Path实例。
     pub fn new1(verbs: Vec<PathVerb>, points: Vec<f32>) -> Self {
+        Self::new_with_tolerance1(verbs, points, PATH_TOLERANCE)
+    }
+
+    /// 创建一个新的Path实例，并指定圆弧拟合的精度。
+    ///
+    /// # 参数
+    /// - `verbs`, `points`: 同`new`。
+    /// - `tolerance`: 圆弧拟合贝塞尔曲线的精度，值越小越精确，圆弧也越多，默认为`PATH_TOLERANCE`。
+    ///
+    /// # 返回值
+    /// 一个新的Path实例。
+    pub fn new_with_tolerance(verbs: Vec<u8>, points: Vec<f32>, tolerance: f32) -> Self {
+        let verbs: Vec<PathVerb> = unsafe { transmute(verbs) };
+
+        Self::new_with_tolerance1(verbs, points, tolerance)
+    }
+
+    /// 创建一个新的Path实例，使用预转换的PathVerb向量，并指定圆弧拟合的精度。
+    pub fn new_with_tolerance1(verbs: Vec<PathVerb>, points: Vec<f32>, tolerance: f32) -> Self {
         let points = points
             .chunks(2)
             .map(|v| Point::new(v[0], v[1]))
@@ -1105,6 +1182,7 @@ impl Path {
             points,
             attribute,
             is_reverse,
+            tolerance,
         };
         r.attribute.is_close = r.is_close();
 
@@ -1139,13 +1217,13 @@ impl Path {
     ///
     /// # 返回值
     /// 包含三个元素的元组：
-    /// 1. 包含所有弧端点的向量。
+    /// 1. 圆弧累加器，包含所有弧端点和拟合的最大误差。
     /// 2. 路径的包围盒。
     /// 3. 哑声记号指示器。
-    fn get_arc_endpoints(&self) -> (Vec<ArcEndpoint>, Aabb, usize) {
+    fn get_arc_endpoints(&self) -> (GlyphyArcAccumulator, Aabb, usize) {
         let mut sink = GlyphVisitor::new(1.0);
         // 设置圆弧拟合贝塞尔曲线的精度，参数值越小精度越高。
-        sink.accumulate.tolerance = 0.01;
+        sink.accumulate.set_tolerance(self.tolerance);
 
         // let is_close = self.is_close();
         compute_outline(
@@ -1168,19 +1246,20 @@ impl Path {
             arcs,
             ..
         } = sink;
-        (accumulate.result, bbox, arcs)
+        (accumulate, bbox, arcs)
     }
 
     /// 计算路径的哈希值。
-    /// 将路径的顶点和动词转换为字节流，哈希后生成唯一标识符。
+    /// 将路径的顶点、动词和拟合精度转换为字节流，哈希后生成唯一标识符。
     pub fn get_hash(&self) -> u64 {
-        let mut key = Vec::with_capacity(self.points.len() * 4);
+        let mut key = Vec::with_capacity(self.points.len() * 4 + 2);
         for (p, v) in self.points.iter().zip(self.verbs.iter()) {
             key.push(p.x);
             key.push(p.y);
             key.push((*v).into());
         }
         key.push(7.0);
+        key.push(self.tolerance);
         let mut hasher = pi_hash::DefaultHasher::default();
         hasher.write(bytemuck::cast_slice(&key));
         hasher.finish()
@@ -1210,7 +1289,7 @@ impl Path {
     /// 计算生成的SVG信息包括包围盒、路径点、区域标记、哈希值和纹理尺寸，
     /// 为后续生成和展示SVG内容做准备。
     pub fn get_svg_info(&self) -> SvgInfo {
-        let (arc_endpoints, binding_box, arcs) = self.get_arc_endpoints();
+        let (accumulate, binding_box, arcs) = self.get_arc_endpoints();
         let size = (binding_box.maxs.x - binding_box.mins.x)
                 .max(binding_box.maxs.y - binding_box.mins.y)
                 .ceil();
@@ -1223,7 +1302,6 @@ impl Path {
                 binding_box.maxs.x,
                 binding_box.maxs.y,
             ],
-            arc_endpoints,
             is_area: self.is_area(),
             is_reverse: if arcs == 1 {
                 Some(!self.is_reverse)
@@ -1232,6 +1310,9 @@ impl Path {
             },
             hash: self.get_hash(),
             tex_size,
+            max_error: accumulate.max_error(),
+            fit_failed: !accumulate.success(),
+            arc_endpoints: accumulate.result,
         }
     }
 
@@ -1245,6 +1326,8 @@ impl Path {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -1268,6 +1351,10 @@ pub struct WasmSvgInfo {
     pub is_area: bool,
     pub hash: String,
     pub tex_size: f32,
+    /// 圆弧拟合贝塞尔曲线的最大误差
+    pub max_error: f32,
+    /// 有曲线超出拟合精度
+    pub fit_failed: bool,
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
@@ -1276,7 +1363,7 @@ pub struct SvgInfo {
     /// SVG绘图的包围盒，用于确定绘制的矩形范围
     pub binding_box: Vec<f32>,
     /// 弧端点的详细信息，用于构建SVG路径
-    arc_endpoints: Vec<ArcEndpoint>,
+    pub(crate) arc_endpoints: Vec<ArcEndpoint>,
     /// 是否表示一个区域，闭合路径通常表示区域
     pub is_area: bool,
     /// 是否被标记为反向的标志，某些情况下可能需要反转
@@ -1285,6 +1372,10 @@ pub struct SvgInfo {
     pub hash: u64,
     /// 纹理尺寸，用于在Web环境中进行绘制
     pub tex_size: f32,
+    /// 圆弧拟合贝塞尔曲线的最大误差，没有曲线时为0
+    pub max_error: f32,
+    /// 有曲线在分段数达到上限时仍超出拟合精度
+    pub fit_failed: bool,
 }
 
 impl SvgInfo {
@@ -1317,6 +1408,8 @@ impl SvgInfo {
             is_reverse,
             hash,
             tex_size: 0.0,
+            max_error: 0.0,
+            fit_failed: false,
         }
     }
 
@@ -1339,6 +1432,25 @@ impl SvgInfo {
         info
     }
 
+    /// 计算近似路径的信息，并保证每个格子的圆弧数不超过预算
+    pub fn compute_near_arcs_with_budget(
+        &self,
+        scale: f32,
+        budget: &CellBudget,
+    ) -> Result<CellInfo, CellBudgetError> {
+        let mut info = compute_near_arcs_with_budget(
+            Aabb::new(
+                Point::new(self.binding_box[0], self.binding_box[1]),
+                Point::new(self.binding_box[2], self.binding_box[3]),
+            ),
+            &self.arc_endpoints,
+            scale,
+            budget,
+        )?;
+        info.is_area = self.is_area;
+        Ok(info)
+    }
+
     pub fn compute_sdf_tex(
         &self,
         tex_size: usize,
@@ -1347,14 +1459,75 @@ impl SvgInfo {
         cur_off: u32,
         scale: f32,
     ) -> SdfInfo2 {
-        // 获取布局信息，如绘制平面的边界和宽度等。
+        self.compute_sdf_tex_range(tex_size, pxrange, pxrange, is_outer_glow, cur_off, scale)
+    }
+
+    /// 生成SDF纹理，内部和外部使用不同的像素范围
+    ///
+    /// 描边较粗或外发光时可加大`outer_pxrange`，细线条可加大`inner_pxrange`
+    pub fn compute_sdf_tex_range(
+        &self,
+        tex_size: usize,
+        inner_pxrange: u32,
+        outer_pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+        scale: f32,
+    ) -> SdfInfo2 {
+        self.compute_sdf_tex_impl(
+            tex_size,
+            inner_pxrange,
+            outer_pxrange,
+            None,
+            is_outer_glow,
+            cur_off,
+            scale,
+        )
+    }
+
+    /// 生成描边SDF纹理，纹理中的距离为到描边带的距离
+    ///
+    /// `stroke_width`为描边宽度（像素），开放路径忽略`align`，总是以路径为中心描边
+    pub fn compute_sdf_tex_stroke(
+        &self,
+        tex_size: usize,
+        pxrange: u32,
+        stroke_width: f32,
+        align: StrokeAlign,
+        cur_off: u32,
+        scale: f32,
+    ) -> SdfInfo2 {
+        let align = if self.is_area { align } else { StrokeAlign::Center };
+        self.compute_sdf_tex_impl(
+            tex_size,
+            pxrange,
+            pxrange,
+            Some((stroke_width, align)),
+            false,
+            cur_off,
+            scale,
+        )
+    }
+
+    fn compute_sdf_tex_impl(
+        &self,
+        tex_size: usize,
+        inner_pxrange: u32,
+        outer_pxrange: u32,
+        stroke: Option<(f32, StrokeAlign)>,
+        is_outer_glow: bool,
+        cur_off: u32,
+        scale: f32,
+    ) -> SdfInfo2 {
+        // 获取布局信息，pxrange传1，得到的distance即为单个像素对应的距离。
         let LayoutInfo {
             plane_bounds,
             atlas_bounds,
-            distance,
+            distance: px_distance,
             tex_size,
             extents,
-        } = self.compute_layout(tex_size, pxrange, cur_off);
+            ..
+        } = self.compute_layout(tex_size, 1, cur_off);
 
         // 获取近似路径的弧线信息。
         let CellInfo { arcs, info, .. } = compute_near_arcs(
@@ -1378,8 +1551,14 @@ impl SvgInfo {
                 Point::new(extents[2], extents[3]),
             ),
             tex_size as usize,
-            distance,
-            if self.is_area { None } else { Some(1.0) },
+            px_distance * inner_pxrange as f32,
+            px_distance * outer_pxrange as f32,
+            match stroke {
+                Some((width, align)) => Some(Stroke::new(width * px_distance, align)),
+                // 开放路径只取距离的绝对值
+                None if !self.is_area => Some(Stroke::new(0.0, StrokeAlign::Center)),
+                None => None,
+            },
             is_outer_glow,
             true,
             self.is_reverse,
@@ -1389,6 +1568,10 @@ impl SvgInfo {
         SdfInfo2 {
             sdf_tex: pixmap,
             tex_size: tex_size,
+            tex_width: tex_size,
+            tex_height: tex_size,
+            inner_range: inner_pxrange as f32,
+            outer_range: outer_pxrange as f32,
             tex_info: TexInfo2 {
                 sdf_offset_x: 0,
                 sdf_offset_y: 0,
@@ -1406,11 +1589,137 @@ impl SvgInfo {
         }
     }
 
-    pub fn compute_sdf_cell(&self, scale: f32) -> SdfInfo {
-        // 计算单个SDF单元格，用于高效拼接和显示。
+    /// 计算非方形的布局信息，纹理宽高按包围盒的宽高比分别计算
+    pub fn compute_layout_rect(&self, tex_size: usize, pxrange: u32, cur_off: u32) -> LayoutInfo {
+        compute_layout_rect(&self.binding_box, tex_size, pxrange, 1, cur_off, true)
+    }
+
+    /// 计算近似路径的信息，格子范围不补齐成正方形
+    pub fn compute_near_arcs_rect(&self, scale: f32) -> CellInfo {
+        let mut info = compute_near_arcs_rect(
+            Aabb::new(
+                Point::new(self.binding_box[0], self.binding_box[1]),
+                Point::new(self.binding_box[2], self.binding_box[3]),
+            ),
+            &self.arc_endpoints,
+            scale,
+        );
+        info.is_area = self.is_area;
+        info
+    }
+
+    /// 生成非方形的SDF纹理，`tex_size`为较长边的大小
+    pub fn compute_sdf_tex_rect(
+        &self,
+        tex_size: usize,
+        pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+        scale: f32,
+    ) -> SdfInfo2 {
+        let LayoutInfo {
+            plane_bounds,
+            atlas_bounds,
+            distance,
+            tex_size,
+            extents,
+            tex_width,
+            tex_height,
+        } = self.compute_layout_rect(tex_size, pxrange, cur_off);
+
+        let CellInfo { arcs, info, .. } = self.compute_near_arcs_rect(scale);
+
+        let pixmap = encode_sdf_rect(
+            &arcs,
+            info,
+            &Aabb::new(
+                Point::new(extents[0], extents[1]),
+                Point::new(extents[2], extents[3]),
+            ),
+            tex_width as usize,
+            tex_height as usize,
+            distance,
+            distance,
+            if self.is_area { None } else { Some(Stroke::new(0.0, StrokeAlign::Center)) },
+            is_outer_glow,
+            true,
+            self.is_reverse,
+        );
+
+        SdfInfo2 {
+            sdf_tex: pixmap,
+            tex_size,
+            tex_width,
+            tex_height,
+            inner_range: pxrange as f32,
+            outer_range: pxrange as f32,
+            tex_info: TexInfo2 {
+                sdf_offset_x: 0,
+                sdf_offset_y: 0,
+                advance: self.binding_box[2] - self.binding_box[0],
+                plane_min_x: plane_bounds[0],
+                plane_min_y: plane_bounds[1],
+                plane_max_x: plane_bounds[2],
+                plane_max_y: plane_bounds[3],
+                atlas_min_x: atlas_bounds[0],
+                atlas_min_y: atlas_bounds[1],
+                atlas_max_x: atlas_bounds[2],
+                atlas_max_y: atlas_bounds[3],
+                char: ' ',
+            },
+        }
+    }
+
+    /// 计算单个SDF单元格，用于高效拼接和显示；宽索引也放不下时返回错误
+    pub fn compute_sdf_cell(&self, scale: f32) -> Result<SdfInfo, EncodeError> {
         let cell = self.compute_near_arcs(scale);
         let blob = cell.encode_blob_arc();
-        blob.encode_tex()
+        blob.try_encode_tex()
+    }
+
+    /// 计算SDF单元格，数据纹理按指定格式编码；大尺寸图形使用`DataFormat::Rgba16`可保持亚像素精度
+    pub fn compute_sdf_cell_with_format(
+        &self,
+        scale: f32,
+        data_format: DataFormat,
+    ) -> Result<SdfInfo, EncodeError> {
+        let cell = self.compute_near_arcs(scale);
+        let blob = cell.encode_blob_arc_with_format(data_format);
+        blob.try_encode_tex()
+    }
+
+    /// 计算SDF单元格，索引按细分得到的k-d树编码，不展开成均匀网格
+    pub fn compute_sdf_kd_cell(&self, scale: f32) -> Result<KdSdfInfo, EncodeError> {
+        self.compute_near_arcs(scale).encode_kd_tree()
+    }
+
+    /// 计算SDF单元格，同时统计细分和编码的质量
+    pub fn encode_with_stats(&self, scale: f32) -> Result<(SdfInfo, EncodeStats), EncodeError> {
+        encode_with_stats(|| self.compute_near_arcs(scale), self.max_error)
+    }
+
+    /// 合并误差在`tolerance`以内的相邻线段和圆弧，在`compute_near_arcs`之前调用可减少圆弧数；
+    /// 哈希值按化简后的端点重新计算
+    pub fn simplify(&mut self, tolerance: f32) {
+        self.arc_endpoints = simplify_endpoints(&self.arc_endpoints, tolerance);
+
+        let mut data = Vec::with_capacity(self.arc_endpoints.len() * 3);
+        for endpoint in &self.arc_endpoints {
+            data.extend_from_slice(&[endpoint.p[0], endpoint.p[1], endpoint.d]);
+        }
+        let mut hasher = pi_hash::DefaultHasher::default();
+        hasher.write(bytemuck::cast_slice(&data));
+        self.hash = hasher.finish();
+    }
+
+    /// 圆弧端点转为三次贝塞尔路径
+    pub fn to_bez_path(&self) -> kurbo::BezPath {
+        endpoints_to_bez_path(&self.arc_endpoints)
+    }
+
+    /// 圆弧端点写成SVG文档
+    pub fn to_svg(&self, options: &SvgExportOptions) -> String {
+        endpoints_to_svg(&self.arc_endpoints, options)
     }
 
     pub fn compute_positions_and_uv(&self, ps: &[f32], uv: &[f32], thickness: f32, out_ps: &mut Vec<f32>, out_uv: &mut Vec<f32>, out_indices: &mut Vec<u16>){
@@ -1637,6 +1946,8 @@ impl SvgInfo {
             is_area: info.is_area,
             hash: info.hash.to_string(),
             tex_size: info.tex_size,
+            max_error: info.max_error,
+            fit_failed: info.fit_failed,
         }
     }
 
@@ -1652,6 +1963,7 @@ impl SvgInfo {
             mut extents,
             distance,
             tex_size,
+            ..
         } = compute_layout(binding_box, tex_size, pxrange, 1, cur_off, true);
         let mut res = Vec::with_capacity(14);
         res.append(&mut plane_bounds);
@@ -1662,11 +1974,68 @@ impl SvgInfo {
         res
     }
 
+    /// 非方形布局，返回值比`compute_layout_of_wasm`多纹理宽高两项
+    pub fn compute_layout_rect_of_wasm(
+        binding_box: &[f32],
+        tex_size: usize,
+        pxrange: u32,
+        cur_off: u32,
+    ) -> Vec<f32> {
+        let LayoutInfo {
+            mut plane_bounds,
+            mut atlas_bounds,
+            mut extents,
+            distance,
+            tex_size,
+            tex_width,
+            tex_height,
+        } = compute_layout_rect(binding_box, tex_size, pxrange, 1, cur_off, true);
+        let mut res = Vec::with_capacity(16);
+        res.append(&mut plane_bounds);
+        res.append(&mut atlas_bounds);
+        res.append(&mut extents);
+        res.push(distance);
+        res.push(tex_size as f32);
+        res.push(tex_width as f32);
+        res.push(tex_height as f32);
+        res
+    }
+
+    pub fn compute_sdf_tex_rect_of_wasm(
+        info: &[u8],
+        tex_size: usize,
+        pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+        scale: f32,
+    ) -> Vec<u8> {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        bitcode::serialize(&info.compute_sdf_tex_rect(tex_size, pxrange, is_outer_glow, cur_off, scale))
+            .unwrap()
+    }
+
     pub fn compute_near_arcs_of_wasm(info: &[u8], scale: f32) -> Vec<u8> {
         let info: SvgInfo = bitcode::deserialize(info).unwrap();
         bitcode::serialize(&info.compute_near_arcs(scale)).unwrap()
     }
 
+    /// 按圆弧预算计算近似路径的信息，超出预算时返回空数组
+    pub fn compute_near_arcs_with_budget_of_wasm(
+        info: &[u8],
+        scale: f32,
+        max_arcs: usize,
+        min_cell_divisor: f32,
+    ) -> Vec<u8> {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        match info.compute_near_arcs_with_budget(scale, &CellBudget::new(max_arcs, min_cell_divisor)) {
+            Ok(info) => bitcode::serialize(&info).unwrap(),
+            Err(e) => {
+                log::warn!("cell budget overflow: {:?}", e);
+                vec![]
+            }
+        }
+    }
+
     pub fn compute_sdf_tex_of_wasm(
         info: &[u8],
         tex_size: usize,
@@ -1680,11 +2049,121 @@ impl SvgInfo {
             .unwrap()
     }
 
+    pub fn compute_sdf_tex_range_of_wasm(
+        info: &[u8],
+        tex_size: usize,
+        inner_pxrange: u32,
+        outer_pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+        scale: f32,
+    ) -> Vec<u8> {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        bitcode::serialize(&info.compute_sdf_tex_range(
+            tex_size,
+            inner_pxrange,
+            outer_pxrange,
+            is_outer_glow,
+            cur_off,
+            scale,
+        ))
+        .unwrap()
+    }
+
+    pub fn compute_sdf_tex_stroke_of_wasm(
+        info: &[u8],
+        tex_size: usize,
+        pxrange: u32,
+        stroke_width: f32,
+        align: StrokeAlign,
+        cur_off: u32,
+        scale: f32,
+    ) -> Vec<u8> {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        bitcode::serialize(&info.compute_sdf_tex_stroke(
+            tex_size,
+            pxrange,
+            stroke_width,
+            align,
+            cur_off,
+            scale,
+        ))
+        .unwrap()
+    }
+
+    /// 计算SDF单元格，宽索引也放不下时返回空数组
     pub fn compute_sdf_cell_of_wasm(info: &[u8], scale: f32) -> Vec<u8> {
         let info: SvgInfo = bitcode::deserialize(info).unwrap();
-        let cell = info.compute_near_arcs(scale);
-        let blob = cell.encode_blob_arc();
-        bitcode::serialize(&blob.encode_tex()).unwrap()
+        match info.compute_sdf_cell(scale) {
+            Ok(r) => bitcode::serialize(&r).unwrap(),
+            Err(err) => {
+                log::warn!("compute_sdf_cell failed: {:?}", err);
+                vec![]
+            }
+        }
+    }
+
+    /// 按指定的数据纹理格式计算SDF单元格，宽索引也放不下时返回空数组
+    pub fn compute_sdf_cell_with_format_of_wasm(
+        info: &[u8],
+        scale: f32,
+        data_format: DataFormat,
+    ) -> Vec<u8> {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        match info.compute_sdf_cell_with_format(scale, data_format) {
+            Ok(r) => bitcode::serialize(&r).unwrap(),
+            Err(err) => {
+                log::warn!("compute_sdf_cell_with_format failed: {:?}", err);
+                vec![]
+            }
+        }
+    }
+
+    /// 计算k-d树索引的SDF单元格，超出宽索引范围时返回空数组
+    pub fn compute_sdf_kd_cell_of_wasm(info: &[u8], scale: f32) -> Vec<u8> {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        match info.compute_sdf_kd_cell(scale) {
+            Ok(r) => bitcode::serialize(&r).unwrap(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// 统计SDF单元格细分和编码的质量，返回序列化后的`EncodeStats`，索引超出范围时返回空数组
+    pub fn encode_stats_of_wasm(info: &[u8], scale: f32) -> Vec<u8> {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        match info.encode_with_stats(scale) {
+            Ok((_, stats)) => bitcode::serialize(&stats).unwrap(),
+            Err(e) => {
+                log::warn!("encode sdf failed: {:?}", e);
+                vec![]
+            }
+        }
+    }
+
+    /// 化简圆弧端点，返回序列化后的化简过的`SvgInfo`
+    pub fn simplify_of_wasm(info: &[u8], tolerance: f32) -> Vec<u8> {
+        let mut info: SvgInfo = bitcode::deserialize(info).unwrap();
+        info.simplify(tolerance);
+        bitcode::serialize(&info).unwrap()
+    }
+
+    /// 圆弧端点写成SVG文档，`endpoints`为是否标出端点
+    pub fn to_svg_of_wasm(info: &[u8], endpoints: bool) -> String {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        info.to_svg(&SvgExportOptions {
+            endpoints,
+            ..Default::default()
+        })
+    }
+
+    /// 细分结果写成SVG文档，画出细分的格子，`endpoints`为是否标出端点
+    pub fn cell_to_svg_of_wasm(info: &[u8], scale: f32, endpoints: bool) -> String {
+        let info: SvgInfo = bitcode::deserialize(info).unwrap();
+        info.compute_near_arcs(scale).to_svg(&SvgExportOptions {
+            endpoints,
+            cells: true,
+            ..Default::default()
+        })
     }
 
     pub fn compute_positions_and_uv_of_wasm(
@@ -1947,6 +2426,33 @@ pub fn extents(mut binding_box: Aabb) -> Aabb {
 
 pub fn compute_near_arcs(view_box: Aabb, endpoints: &Vec<ArcEndpoint>, scale: f32) -> CellInfo {
     let extents = compute_cell_range(view_box, scale);
+    compute_near_arcs_of_range(extents, endpoints, &CellBudget::unlimited()).0
+}
+
+/// 计算近段弧的信息，格子范围不补齐成正方形，用于非方形纹理
+pub fn compute_near_arcs_rect(view_box: Aabb, endpoints: &Vec<ArcEndpoint>, scale: f32) -> CellInfo {
+    let extents = compute_cell_range_rect(view_box, scale);
+    compute_near_arcs_of_range(extents, endpoints, &CellBudget::unlimited()).0
+}
+
+/// 计算近段弧的信息，格子的圆弧数超出预算时继续细分，到达最小格子仍超出时返回错误
+pub fn compute_near_arcs_with_budget(
+    view_box: Aabb,
+    endpoints: &Vec<ArcEndpoint>,
+    scale: f32,
+    budget: &CellBudget,
+) -> Result<CellInfo, CellBudgetError> {
+    let extents = compute_cell_range(view_box, scale);
+    let (info, overflows) = compute_near_arcs_of_range(extents, endpoints, budget);
+    CellBudgetError::check(budget, overflows)?;
+    Ok(info)
+}
+
+fn compute_near_arcs_of_range(
+    extents: Aabb,
+    endpoints: &Vec<ArcEndpoint>,
+    budget: &CellBudget,
+) -> (CellInfo, Vec<(usize, Aabb)>) {
     // log::debug!("extents: {:?}", extents);
     // let extents = compute_cell_range(extents, scale);
     let mut min_width = f32::INFINITY;
@@ -1978,6 +2484,7 @@ pub fn compute_near_arcs(view_box: Aabb, endpoints: &Vec<ArcEndpoint>, scale: f3
     let mut result_arcs = vec![];
     let mut temp = Vec::with_capacity(arcs.len());
     let mut tempidxs = vec![];
+    let mut overflows = vec![];
     // log::debug!("arcs:{:?}", arcs.len());
     recursion_near_arcs_of_cell(
         // &near_arcs,
@@ -1995,16 +2502,21 @@ pub fn compute_near_arcs(view_box: Aabb, endpoints: &Vec<ArcEndpoint>, scale: f3
         &mut tempsegment,
         // id,
         &mut tempidxs,
+        budget,
+        &mut overflows,
     );
 
-    CellInfo {
-        extents,
-        arcs: near_arcs,
-        info: result_arcs,
-        min_width,
-        min_height,
-        is_area: true,
-    }
+    (
+        CellInfo {
+            extents,
+            arcs: near_arcs,
+            info: result_arcs,
+            min_width,
+            min_height,
+            is_area: true,
+        },
+        overflows,
+    )
 }
 
 #[test]
@@ -2033,3 +2545,160 @@ fn test() {
     // log::debug!("圆心角（弧度）：{}", theta);
     // log::debug!("圆心角（度）：{}", theta_degrees);
 }
+
+#[test]
+fn test_cell_budget() {
+    let info = Rect::new(0.0, 0.0, 10.0, 10.0).get_svg_info();
+    let cells = info.compute_near_arcs(1.0);
+    let budgeted = info.compute_near_arcs_with_budget(1.0, &CellBudget::default()).unwrap();
+    assert!(budgeted.info.iter().all(|(arcs, _)| arcs.len() <= CellBudget::default().max_arcs));
+    assert_eq!(cells.info.len(), budgeted.info.len());
+
+    // 角上的格子至少有两段圆弧，细分到最小格子也无法满足
+    let err = info.compute_near_arcs_with_budget(1.0, &CellBudget::new(1, 32.0)).unwrap_err();
+    assert_eq!(err.max_arcs, 1);
+    assert!(!err.cells.is_empty());
+}
+
+#[test]
+fn test_tolerance() {
+    let coarse = Ellipse::new_with_tolerance(50.0, 50.0, 40.0, 20.0, 1.0).get_svg_info();
+    let fine = Ellipse::new_with_tolerance(50.0, 50.0, 40.0, 20.0, 0.01).get_svg_info();
+    assert!(!coarse.fit_failed && !fine.fit_failed);
+    assert!(coarse.max_error <= 1.0 && fine.max_error <= 0.01);
+    assert!(fine.arc_endpoints.len() > coarse.arc_endpoints.len());
+    // 拟合精度不同的同一椭圆不能共用缓存
+    assert_ne!(coarse.hash, fine.hash);
+    // 圆弧的中点在真正的椭圆附近，而不只是在展开的贝塞尔曲线附近
+    for w in fine.arc_endpoints.windows(2) {
+        if w[1].d == GLYPHY_INFINITY {
+            continue;
+        }
+        let arc = Arc::new(Point::new(w[0].p[0], w[0].p[1]), Point::new(w[1].p[0], w[1].p[1]), w[1].d);
+        let c = *arc.center();
+        let mid = Point::new((arc.p0.x + arc.p1.x) * 0.5, (arc.p0.y + arc.p1.y) * 0.5);
+        let m = c + (mid - c).normalize() * arc.radius;
+        let (x, y) = ((m.x - 50.0) / 40.0, (m.y - 50.0) / 20.0);
+        assert!(((x * x + y * y).sqrt() - 1.0).abs() * 20.0 <= 0.01);
+    }
+
+    let verbs = vec![PathVerb::MoveTo, PathVerb::CubicTo];
+    let points = vec![0.0, 0.0, 30.0, 60.0, 70.0, -60.0, 100.0, 0.0];
+    let default = Path::new1(verbs.clone(), points.clone()).get_svg_info();
+    assert!(default.max_error > 0.0 && default.max_error <= PATH_TOLERANCE);
+    let fine = Path::new_with_tolerance1(verbs, points, 0.001).get_svg_info();
+    assert!(!fine.fit_failed && fine.max_error <= 0.001);
+    assert!(fine.arc_endpoints.len() > default.arc_endpoints.len());
+    assert_ne!(fine.hash, default.hash);
+}
+
+#[test]
+fn test_rect() {
+    // 4:1的矩形，较长边32像素，每像素1.25
+    let info = Rect::new(0.0, 0.0, 40.0, 10.0).get_svg_info();
+    let layout = info.compute_layout_rect(32, 2, 1);
+    assert_eq!((layout.tex_width, layout.tex_height, layout.tex_size), (34, 10, 34));
+    assert_eq!(layout.atlas_bounds, vec![1.0, 1.0, 33.0, 9.0]);
+    assert_eq!(layout.extents, vec![-1.25, -1.25, 41.25, 11.25]);
+    assert_eq!(layout.distance, 2.5);
+
+    // 与方形纹理的像素大小相同，非方形纹理就是方形纹理的前几行
+    let rect = info.compute_sdf_tex_rect(32, 2, false, 1, 1.0);
+    let square = info.compute_sdf_tex(32, 2, false, 1, 1.0);
+    assert_eq!(rect.sdf_tex.len(), 34 * 10);
+    assert_eq!(rect.sdf_tex[..], square.sdf_tex[..34 * 10]);
+    // 中心在矩形内，四角在矩形外
+    assert_eq!(rect.sdf_tex[5 * 34 + 17], 255);
+    assert!(rect.sdf_tex[0] < 128);
+
+    // 格子的细分只与字形的形状有关，与缩放无关
+    let cells = |k: f32| {
+        Rect::new(0.0, 0.0, 40.0 * k, 10.0 * k)
+            .get_svg_info()
+            .compute_near_arcs_rect(1.0)
+            .info
+            .len()
+    };
+    assert_eq!(cells(0.1), cells(1.0));
+    assert_eq!(cells(100.0), cells(1.0));
+}
+
+#[test]
+fn test_range() {
+    // 每像素1.25，四周留8像素
+    let info = Rect::new(0.0, 0.0, 40.0, 40.0).get_svg_info();
+    let symmetric = info.compute_sdf_tex_range(32, 2, 2, false, 8, 1.0);
+    let inner = info.compute_sdf_tex_range(32, 4, 2, false, 8, 1.0);
+    let outer = info.compute_sdf_tex_range(32, 2, 4, false, 8, 1.0);
+    assert_eq!((inner.inner_range, inner.outer_range), (4.0, 2.0));
+    assert_eq!((outer.inner_range, outer.outer_range), (2.0, 4.0));
+
+    // 边缘为127，范围加倍时到边缘的差值减半；另一侧不受影响
+    let half = |v: u8| 127.0 + (v as f32 - 127.0) * 0.5;
+    let (mut inside, mut outside) = (0, 0);
+    for (i, &v) in symmetric.sdf_tex.iter().enumerate() {
+        if v > 127 && v < 255 {
+            inside += 1;
+            assert!((inner.sdf_tex[i] as f32 - half(v)).abs() <= 1.0, "{}", i);
+            assert_eq!(outer.sdf_tex[i], v);
+        } else if v < 127 && v > 0 {
+            outside += 1;
+            assert!((outer.sdf_tex[i] as f32 - half(v)).abs() <= 1.0, "{}", i);
+            assert_eq!(inner.sdf_tex[i], v);
+        }
+    }
+    assert!(inside > 0 && outside > 0);
+}
+
+#[test]
+fn test_stroke() {
+    // 到轮廓的距离（内部为负）转换为到描边带的距离
+    let cases = [
+        (StrokeAlign::Inside, [-4.0, -2.0, 0.0, 2.0], [0.0, -2.0, 0.0, 2.0]),
+        (StrokeAlign::Center, [-4.0, -2.0, 0.0, 2.0], [2.0, 0.0, -2.0, 0.0]),
+        (StrokeAlign::Outside, [-2.0, 0.0, 2.0, 4.0], [2.0, 0.0, -2.0, 0.0]),
+    ];
+    for (align, sdf, expected) in cases {
+        let stroke = Stroke::new(4.0, align);
+        for (sdf, expected) in sdf.iter().zip(expected) {
+            assert_eq!(stroke.distance(*sdf), expected, "{:?} {}", align, sdf);
+        }
+    }
+    assert_eq!(Stroke::new(0.0, StrokeAlign::Center).distance(-3.0), 3.0);
+
+    // 每像素1.25，四周留8像素，第i列像素中心的x为 -10 + (i + 0.5) * 1.25
+    let center_x = |i: usize| -10.0 + (i as f32 + 0.5) * 1.25;
+    let info = Rect::new(0.0, 0.0, 40.0, 40.0).get_svg_info();
+    // 描边宽4像素（5个单位），矩形左边在x = 0
+    for (align, lo, hi) in [
+        (StrokeAlign::Inside, 0.0, 5.0),
+        (StrokeAlign::Center, -2.5, 2.5),
+        (StrokeAlign::Outside, -5.0, 0.0),
+    ] {
+        let sdf = info.compute_sdf_tex_stroke(32, 2, 4.0, align, 8, 1.0);
+        let w = sdf.tex_width as usize;
+        let row = &sdf.sdf_tex[w * (w / 2)..w * (w / 2 + 1)];
+        // 描边带内的像素（大于127）在左边的位置和数量
+        let band: Vec<usize> = (0..w / 2).filter(|i| row[*i] > 127).collect();
+        assert_eq!(band.len(), 4, "{:?}", align);
+        assert_eq!(center_x(band[0]), lo + 0.625, "{:?}", align);
+        assert_eq!(center_x(band[3]), hi - 0.625, "{:?}", align);
+        // 描边带外两侧都是到描边带的距离，矩形中心远离描边带
+        assert!(row[band[0] - 1] < 127 && row[band[3] + 1] < 127);
+        assert_eq!(row[w / 2], 0);
+        assert!((0..w).all(|i| row[i] == row[w - 1 - i]));
+    }
+
+    // 开放路径按宽度为0的居中描边处理：没有像素在描边带内，两侧对称，线上为127
+    let line = Path::new1(vec![PathVerb::MoveTo, PathVerb::LineTo], vec![0.0, 20.0, 40.0, 20.0])
+        .get_svg_info();
+    assert!(!line.is_area);
+    let sdf = line.compute_sdf_tex(32, 2, false, 8, 1.0);
+    let w = sdf.tex_width as usize;
+    for y in 0..sdf.tex_height as usize {
+        // 第8行的上边在y = 20
+        let d = ((y as f32 + 0.5) - 8.0).abs();
+        let expected = ((1.0 - d / 2.0) * 127.0).round().max(0.0) as u8;
+        assert_eq!(sdf.sdf_tex[y * w + w / 2], expected, "{}", y);
+    }
+}
@@ -6,8 +6,7 @@
 //! - 几何图元（圆弧、线段）的转换和优化
 //! - WebAssembly支持
 use core::fmt;
-use std::collections::HashMap;
-// use std::collections::BTreeMap as HashMap;
+use std::collections::BTreeMap;
 
 // use ab_glyph_rasterizer::Rasterizer;
 use allsorts::{
@@ -25,10 +24,16 @@ use serde::{
 use wasm_bindgen::prelude::wasm_bindgen;
 
 use crate::{
+    export::outline::{
+        arcs_to_bez_path, cell_info_to_svg, endpoints_to_bez_path, endpoints_to_svg,
+        SvgExportOptions,
+    },
     glyphy::{
-        blob::{travel_data, BlobArc},
+        blob::{travel_data, BlobArc, CellBudget, CellBudgetError, EncodeError, SdfInfo},
         geometry::{aabb::Aabb, arcs::GlyphyArcAccumulator},
         sdf::glyphy_sdf_from_arc_list3,
+        simplify::simplify_endpoints,
+        stats::{encode_with_stats, EncodeStats},
         util::float2_equals,
     },
     Point,
@@ -39,7 +44,7 @@ use std::hash::Hasher;
 use crate::{
     font::FontFace,
     glyphy::{
-        blob::{line_encode, snap, Extents, UnitArc},
+        blob::{DataFormat, Extents, UnitArc},
         geometry::{
             arc::{Arc, ArcEndpoint},
             line::Line,
@@ -98,8 +103,16 @@ pub struct SdfInfo2 {
     pub tex_info: TexInfo2,
     /// SDF纹理的具体数据，使用Vec<u8>存储
     pub sdf_tex: Vec<u8>,
-    /// 纹理的大小，使用u32表示
+    /// 纹理的大小，使用u32表示（非方形纹理时为较长边）
     pub tex_size: u32,
+    /// 纹理的宽度
+    pub tex_width: u32,
+    /// 纹理的高度
+    pub tex_height: u32,
+    /// 内部距离范围（像素），alpha从127到254对应的距离
+    pub inner_range: f32,
+    /// 外部距离范围（像素），alpha从127到0对应的距离
+    pub outer_range: f32,
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(getter_with_clone))]
@@ -116,7 +129,11 @@ pub struct OutlineInfo {
     pub extents: Vec<f32>,
     // #[cfg(feature = "debug")]
     pub svg_paths: Vec<String>,
-    pub is_cw: bool
+    pub is_cw: bool,
+    /// 圆弧拟合贝塞尔曲线的最大误差
+    pub max_error: f32,
+    /// 有曲线在分段数达到上限时仍超出拟合精度
+    pub fit_failed: bool,
 }
 
 impl OutlineInfo {
@@ -141,6 +158,59 @@ impl OutlineInfo {
         r
     }
 
+    /// 计算字符的附近圆弧，并保证每个格子的圆弧数不超过预算
+    ///
+    /// # 参数
+    /// * `scale` - 缩放比例因子，用于调整计算过程中的比例
+    /// * `budget` - 每个格子的圆弧预算
+    ///
+    /// # 返回
+    /// * `Result<CellInfo, CellBudgetError>` - 到达最小格子仍超出预算时返回超出预算的格子
+    pub fn compute_near_arcs_with_budget(
+        &self,
+        scale: f32,
+        budget: &CellBudget,
+    ) -> Result<CellInfo, CellBudgetError> {
+        FontFace::compute_near_arcs_with_budget(
+            Aabb::new(
+                Point::new(self.extents[0], self.extents[1]),
+                Point::new(self.extents[2], self.extents[3]),
+            ),
+            scale,
+            &self.endpoints,
+            budget,
+        )
+    }
+
+    /// 计算字符的SDF单元格，同时统计细分和编码的质量
+    ///
+    /// # 参数
+    /// * `scale` - 缩放比例因子，用于调整计算过程中的比例
+    ///
+    /// # 返回
+    /// * `Result<(SdfInfo, EncodeStats), EncodeError>` - 编码的纹理和质量统计
+    pub fn encode_with_stats(&self, scale: f32) -> Result<(SdfInfo, EncodeStats), EncodeError> {
+        encode_with_stats(|| self.compute_near_arcs(scale), self.max_error)
+    }
+
+    /// 合并误差在`tolerance`以内的相邻线段和圆弧，在`compute_near_arcs`之前调用可减少圆弧数
+    ///
+    /// # 参数
+    /// * `tolerance` - 合并后的圆弧到原轮廓的最大距离，单位与轮廓坐标相同
+    pub fn simplify(&mut self, tolerance: f32) {
+        self.endpoints = simplify_endpoints(&self.endpoints, tolerance);
+    }
+
+    /// 轮廓转为三次贝塞尔路径
+    pub fn to_bez_path(&self) -> kurbo::BezPath {
+        endpoints_to_bez_path(&self.endpoints)
+    }
+
+    /// 轮廓写成SVG文档；字体轮廓的y轴向上，通常需要设置`options.flip_y`
+    pub fn to_svg(&self, options: &SvgExportOptions) -> String {
+        endpoints_to_svg(&self.endpoints, options)
+    }
+
     /// 计算字符的布局信息，包括字符在纹理中的位置、大小等
     ///
     /// # 参数
@@ -180,13 +250,92 @@ impl OutlineInfo {
         is_outer_glow: bool,
         cur_off: u32,
     ) -> SdfInfo2 {
+        self.compute_sdf_tex_range(result_arcs, tex_size, pxrange, pxrange, is_outer_glow, cur_off)
+    }
+
+    /// 生成字符的SDF纹理信息，内部和外部使用不同的距离范围
+    ///
+    /// # 参数
+    /// * `result_arcs` - 包含字符轮廓的圆弧信息
+    /// * `tex_size` - 纹理的大小
+    /// * `inner_pxrange` - 内部的像素范围
+    /// * `outer_pxrange` - 外部的像素范围
+    /// * `is_outer_glow` - 是否为外发光效果
+    /// * `cur_off` - 当前偏移量
+    ///
+    /// # 返回
+    /// * `SdfInfo2` - `inner_range`、`outer_range`分别为内外的像素范围
+    pub fn compute_sdf_tex_range(
+        &self,
+        result_arcs: CellInfo,
+        tex_size: usize,
+        inner_pxrange: u32,
+        outer_pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+    ) -> SdfInfo2 {
+        self.compute_sdf_tex_impl(
+            result_arcs,
+            tex_size,
+            inner_pxrange,
+            outer_pxrange,
+            None,
+            is_outer_glow,
+            cur_off,
+        )
+    }
+
+    /// 生成字符的描边SDF纹理，纹理中的距离为到描边带的距离，可直接渲染空心字
+    ///
+    /// # 参数
+    /// * `result_arcs` - 包含字符轮廓的圆弧信息
+    /// * `tex_size` - 纹理的大小
+    /// * `pxrange` - 像素范围
+    /// * `stroke_width` - 描边宽度（像素），外侧描边时需保证`cur_off`足够容纳
+    /// * `align` - 描边的对齐方式
+    /// * `cur_off` - 当前偏移量
+    ///
+    /// # 返回
+    /// * `SdfInfo2` - 包含SDF纹理数据和布局信息的结构体
+    pub fn compute_sdf_tex_stroke(
+        &self,
+        result_arcs: CellInfo,
+        tex_size: usize,
+        pxrange: u32,
+        stroke_width: f32,
+        align: StrokeAlign,
+        cur_off: u32,
+    ) -> SdfInfo2 {
+        self.compute_sdf_tex_impl(
+            result_arcs,
+            tex_size,
+            pxrange,
+            pxrange,
+            Some((stroke_width, align)),
+            false,
+            cur_off,
+        )
+    }
+
+    fn compute_sdf_tex_impl(
+        &self,
+        result_arcs: CellInfo,
+        tex_size: usize,
+        inner_pxrange: u32,
+        outer_pxrange: u32,
+        stroke: Option<(f32, StrokeAlign)>,
+        is_outer_glow: bool,
+        cur_off: u32,
+    ) -> SdfInfo2 {
+        // pxrange传1，得到的distance即为单个像素对应的距离
         let LayoutInfo {
             plane_bounds,
             atlas_bounds,
-            distance,
+            distance: px_distance,
             tex_size,
             extents,
-        } = self.compute_layout(tex_size, pxrange, cur_off);
+            ..
+        } = self.compute_layout(tex_size, 1, cur_off);
         let extents = Aabb::new(
             Point::new(extents[0], extents[1]),
             Point::new(extents[2], extents[3]),
@@ -197,6 +346,171 @@ impl OutlineInfo {
             info,
             &extents,
             tex_size as usize,
+            px_distance * inner_pxrange as f32,
+            px_distance * outer_pxrange as f32,
+            stroke.map(|(width, align)| Stroke::new(width * px_distance, align)),
+            is_outer_glow,
+            false,
+            Some(!self.is_cw),
+        );
+
+        SdfInfo2 {
+            tex_info: TexInfo2 {
+                char: self.char,
+                advance: self.advance as f32,
+                sdf_offset_x: 0,
+                sdf_offset_y: 0,
+                plane_min_x: plane_bounds[0],
+                plane_min_y: plane_bounds[1],
+                plane_max_x: plane_bounds[2],
+                plane_max_y: plane_bounds[3],
+                atlas_min_x: atlas_bounds[0],
+                atlas_min_y: atlas_bounds[1],
+                atlas_max_x: atlas_bounds[2],
+                atlas_max_y: atlas_bounds[3],
+            },
+            sdf_tex: pixmap,
+            tex_size,
+            tex_width: tex_size,
+            tex_height: tex_size,
+            inner_range: inner_pxrange as f32,
+            outer_range: outer_pxrange as f32,
+        }
+    }
+
+    /// 计算字符的附近圆弧，格子范围不补齐成正方形，配合`compute_sdf_tex_rect`使用
+    ///
+    /// # 参数
+    /// * `scale` - 缩放比例因子
+    ///
+    /// # 返回
+    /// * `CellInfo` - 包含字符轮廓的圆弧集合和相关元数据
+    pub fn compute_near_arcs_rect(&self, scale: f32) -> CellInfo {
+        FontFace::compute_near_arcs_rect(
+            Aabb::new(
+                Point::new(self.extents[0], self.extents[1]),
+                Point::new(self.extents[2], self.extents[3]),
+            ),
+            scale,
+            &self.endpoints,
+        )
+    }
+
+    /// 计算字符的非方形布局信息
+    ///
+    /// # 参数
+    /// * `tex_size` - 较长边的纹理大小
+    /// * `pxrange` - 像素范围
+    /// * `cur_off` - 当前偏移量
+    ///
+    /// # 返回
+    /// * `LayoutInfo` - `tex_width`、`tex_height`为纹理宽高，`atlas_bounds`为紧凑矩形
+    pub fn compute_layout_rect(&self, tex_size: usize, pxrange: u32, cur_off: u32) -> LayoutInfo {
+        compute_layout_rect(
+            &self.extents,
+            tex_size,
+            pxrange,
+            self.units_per_em,
+            cur_off,
+            false,
+        )
+    }
+
+    /// 按固定的每em像素数计算字符的布局信息
+    ///
+    /// # 参数
+    /// * `px_per_em` - 每em对应的像素数
+    /// * `pxrange` - 像素范围
+    /// * `cur_off` - 当前偏移量
+    ///
+    /// # 返回
+    /// * `LayoutInfo` - `tex_width`、`tex_height`为纹理宽高，`atlas_bounds`为紧凑矩形
+    pub fn compute_layout_px_per_em(&self, px_per_em: f32, pxrange: u32, cur_off: u32) -> LayoutInfo {
+        compute_layout_px_per_em(
+            &self.extents,
+            px_per_em,
+            pxrange,
+            self.units_per_em,
+            cur_off,
+            false,
+        )
+    }
+
+    /// 生成字符的非方形SDF纹理
+    ///
+    /// # 参数
+    /// * `result_arcs` - `compute_near_arcs_rect`的结果
+    /// * `tex_size` - 较长边的纹理大小
+    /// * `pxrange` - 像素范围
+    /// * `is_outer_glow` - 是否为外发光效果
+    /// * `cur_off` - 当前偏移量
+    ///
+    /// # 返回
+    /// * `SdfInfo2` - 纹理大小为`tex_width * tex_height`
+    pub fn compute_sdf_tex_rect(
+        &self,
+        result_arcs: CellInfo,
+        tex_size: usize,
+        pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+    ) -> SdfInfo2 {
+        let layout = self.compute_layout_rect(tex_size, pxrange, cur_off);
+        self.compute_sdf_tex_of_layout(result_arcs, layout, pxrange, is_outer_glow)
+    }
+
+    /// 按固定的每em像素数生成字符的SDF纹理，所有字符的像素/em相同，可导出为BMFont
+    ///
+    /// # 参数
+    /// * `result_arcs` - `compute_near_arcs_rect`的结果
+    /// * `px_per_em` - 每em对应的像素数
+    /// * `pxrange` - 像素范围
+    /// * `is_outer_glow` - 是否为外发光效果
+    /// * `cur_off` - 当前偏移量
+    ///
+    /// # 返回
+    /// * `SdfInfo2` - 纹理大小为`tex_width * tex_height`
+    pub fn compute_sdf_tex_px_per_em(
+        &self,
+        result_arcs: CellInfo,
+        px_per_em: f32,
+        pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+    ) -> SdfInfo2 {
+        let layout = self.compute_layout_px_per_em(px_per_em, pxrange, cur_off);
+        self.compute_sdf_tex_of_layout(result_arcs, layout, pxrange, is_outer_glow)
+    }
+
+    // 按非方形布局生成SDF纹理
+    fn compute_sdf_tex_of_layout(
+        &self,
+        result_arcs: CellInfo,
+        layout: LayoutInfo,
+        pxrange: u32,
+        is_outer_glow: bool,
+    ) -> SdfInfo2 {
+        let LayoutInfo {
+            plane_bounds,
+            atlas_bounds,
+            distance,
+            tex_size,
+            extents,
+            tex_width,
+            tex_height,
+        } = layout;
+        let extents = Aabb::new(
+            Point::new(extents[0], extents[1]),
+            Point::new(extents[2], extents[3]),
+        );
+        let CellInfo { arcs, info, .. } = result_arcs;
+        let pixmap = encode_sdf_rect(
+            &arcs,
+            info,
+            &extents,
+            tex_width as usize,
+            tex_height as usize,
+            distance,
             distance,
             None,
             is_outer_glow,
@@ -221,6 +535,10 @@ impl OutlineInfo {
             },
             sdf_tex: pixmap,
             tex_size,
+            tex_width,
+            tex_height,
+            inner_range: pxrange as f32,
+            outer_range: pxrange as f32,
         }
     }
 }
@@ -240,6 +558,83 @@ impl OutlineInfo {
         bitcode::serialize(&outline.compute_near_arcs(scale)).unwrap()
     }
 
+    /// 通过 wasm 绑定按圆弧预算计算字符的附近圆弧信息
+    ///
+    /// # 参数
+    /// * `outline` - 轮廓信息的字节数组输入
+    /// * `scale` - 缩放比例因子
+    /// * `max_arcs` - 每个格子最多的圆弧数
+    /// * `min_cell_divisor` - 最小格子为字形尺寸的 1/min_cell_divisor
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的圆弧信息；到达最小格子仍超出预算时为空
+    pub fn compute_near_arcs_with_budget_of_wasm(
+        outline: &[u8],
+        scale: f32,
+        max_arcs: usize,
+        min_cell_divisor: f32,
+    ) -> Vec<u8> {
+        let outline: OutlineInfo = bitcode::deserialize(outline).unwrap();
+        match outline.compute_near_arcs_with_budget(scale, &CellBudget::new(max_arcs, min_cell_divisor)) {
+            Ok(info) => bitcode::serialize(&info).unwrap(),
+            Err(e) => {
+                log::warn!("cell budget overflow: {:?}", e);
+                vec![]
+            }
+        }
+    }
+
+    /// 通过 wasm 绑定统计字符细分和编码的质量
+    ///
+    /// # 参数
+    /// * `outline` - 序列化后的轮廓信息
+    /// * `scale` - 缩放比例因子
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的`EncodeStats`；索引超出范围时为空
+    pub fn encode_stats_of_wasm(outline: &[u8], scale: f32) -> Vec<u8> {
+        let outline: OutlineInfo = bitcode::deserialize(outline).unwrap();
+        match outline.encode_with_stats(scale) {
+            Ok((_, stats)) => bitcode::serialize(&stats).unwrap(),
+            Err(e) => {
+                log::warn!("encode sdf failed: {:?}", e);
+                vec![]
+            }
+        }
+    }
+
+    /// 通过 wasm 绑定化简字符轮廓
+    ///
+    /// # 参数
+    /// * `outline` - 序列化后的轮廓信息
+    /// * `tolerance` - 合并后的圆弧到原轮廓的最大距离
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的化简过的轮廓信息
+    pub fn simplify_of_wasm(outline: &[u8], tolerance: f32) -> Vec<u8> {
+        let mut outline: OutlineInfo = bitcode::deserialize(outline).unwrap();
+        outline.simplify(tolerance);
+        bitcode::serialize(&outline).unwrap()
+    }
+
+    /// 通过 wasm 绑定把字符轮廓写成SVG文档
+    ///
+    /// # 参数
+    /// * `outline` - 序列化后的轮廓信息
+    /// * `endpoints` - 是否标出圆弧端点
+    /// * `flip_y` - 是否上下翻转，字体轮廓的y轴向上
+    ///
+    /// # 返回
+    /// * `String` - SVG文档
+    pub fn to_svg_of_wasm(outline: &[u8], endpoints: bool, flip_y: bool) -> String {
+        let outline: OutlineInfo = bitcode::deserialize(outline).unwrap();
+        outline.to_svg(&SvgExportOptions {
+            endpoints,
+            flip_y,
+            ..Default::default()
+        })
+    }
+
     /// 通过 wasm 绑定计算字符的 SDF 纹理，并返回序列化后的字节数组
     ///
     /// # 参数
@@ -264,15 +659,53 @@ impl OutlineInfo {
         is_outer_glow: bool,
         cur_off: u32,
         is_cw: bool
+    ) -> Vec<u8> {
+        Self::compute_sdf_tex_range_of_wasm(
+            result_arcs,
+            extents,
+            units_per_em,
+            advance,
+            tex_size,
+            pxrange,
+            pxrange,
+            is_outer_glow,
+            cur_off,
+            is_cw,
+        )
+    }
+
+    /// 通过 wasm 绑定计算字符的 SDF 纹理，内部和外部使用不同的像素范围
+    ///
+    /// # 参数
+    /// * `inner_pxrange` - 内部的像素范围
+    /// * `outer_pxrange` - 外部的像素范围
+    ///
+    /// 其余参数与`compute_sdf_tex_of_wasm`相同
+    ///
+    /// # 返回
+    /// * `Vec<u8>` - 序列化后的 SDF 纹理信息字节数组
+    pub fn compute_sdf_tex_range_of_wasm(
+        result_arcs: &[u8],
+        extents: &[f32],
+        units_per_em: u16,
+        advance: u16,
+        tex_size: usize,
+        inner_pxrange: u32,
+        outer_pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+        is_cw: bool
     ) -> Vec<u8> {
         let result_arcs: CellInfo = bitcode::deserialize(result_arcs).unwrap();
+        // pxrange传1，得到的distance即为单个像素对应的距离
         let LayoutInfo {
             plane_bounds,
             atlas_bounds,
-            distance,
+            distance: px_distance,
             tex_size,
             extents,
-        } = compute_layout(extents, tex_size, pxrange, units_per_em, cur_off, false);
+            ..
+        } = compute_layout(extents, tex_size, 1, units_per_em, cur_off, false);
         let extents = Aabb::new(
             Point::new(extents[0], extents[1]),
             Point::new(extents[2], extents[3]),
@@ -283,7 +716,8 @@ impl OutlineInfo {
             info, // 传递CellInfo的info字段，可能包含其他元数据信息
             &extents, // 传递字符的范围信息，表示字符在平面中的位置和大小
             tex_size as usize, // 纹理的大小，确保类型正确
-            distance, // 用于生成距离场的距离值
+            px_distance * inner_pxrange as f32, // 内部的距离范围
+            px_distance * outer_pxrange as f32, // 外部的距离范围
             None, // 可能是其他参数的默认值，暂时保留为None
             is_outer_glow, // 传递是否应用外发光效果的标志
             false, // 可能的其他参数，默认为false
@@ -307,9 +741,127 @@ impl OutlineInfo {
             }, // 结束TexInfo2结构体的构造
             sdf_tex: pixmap, // 将pixmap设置为SDF纹理数据字段
             tex_size, // 设置纹理大小，这里直接使用计算出的tex_size值，并确保类型正确可达u32类型，若有需要可以进行调整，例如tex_size as u32
+            tex_width: tex_size, // 方形纹理，宽高一致
+            tex_height: tex_size,
+            inner_range: inner_pxrange as f32,
+            outer_range: outer_pxrange as f32,
         }) // 结束SdfInfo2结构体的构造，并将其作为参数传递给serialize函数进行序列化处理
         .unwrap() // 使用unwrap处理Result，假设序列化总是成功，或者根据需要替换为error处理机制
-    } // 结束compute_sdf_tex_of_wasm函数定义，返回序列化后的字节数组
+    }
+
+    /// 通过 wasm 绑定计算字符的描边 SDF 纹理，并返回序列化后的字节数组
+    ///
+    /// # 参数
+    /// * `stroke_width` - 描边宽度（像素）
+    /// * `align` - 描边的对齐方式
+    ///
+    /// 其余参数与`compute_sdf_tex_of_wasm`相同
+    pub fn compute_sdf_tex_stroke_of_wasm(
+        result_arcs: &[u8],
+        extents: &[f32],
+        units_per_em: u16,
+        advance: u16,
+        tex_size: usize,
+        pxrange: u32,
+        stroke_width: f32,
+        align: StrokeAlign,
+        cur_off: u32,
+        is_cw: bool
+    ) -> Vec<u8> {
+        let result_arcs: CellInfo = bitcode::deserialize(result_arcs).unwrap();
+        // 只用到布局相关的字段，不需要轮廓端点
+        let outline = OutlineInfo {
+            char: ' ',
+            endpoints: vec![],
+            bbox: vec![],
+            advance,
+            units_per_em,
+            extents: extents.to_vec(),
+            svg_paths: vec![],
+            is_cw,
+            max_error: 0.0,
+            fit_failed: false,
+        };
+        let mut info = outline.compute_sdf_tex_stroke(
+            result_arcs,
+            tex_size,
+            pxrange,
+            stroke_width,
+            align,
+            cur_off,
+        );
+        info.tex_info.advance = advance as f32 / units_per_em as f32;
+        bitcode::serialize(&info).unwrap()
+    }
+
+    /// 通过 wasm 绑定计算字符的附近圆弧信息（非方形范围），并返回序列化后的字节数组
+    pub fn compute_near_arcs_rect_of_wasm(outline: &[u8], scale: f32) -> Vec<u8> {
+        let outline: OutlineInfo = bitcode::deserialize(outline).unwrap();
+        bitcode::serialize(&outline.compute_near_arcs_rect(scale)).unwrap()
+    }
+
+    /// 通过 wasm 绑定计算字符的非方形 SDF 纹理，参数同`compute_sdf_tex_of_wasm`，
+    /// `result_arcs`需由`compute_near_arcs_rect_of_wasm`得到
+    pub fn compute_sdf_tex_rect_of_wasm(
+        result_arcs: &[u8],
+        extents: &[f32],
+        units_per_em: u16,
+        advance: u16,
+        tex_size: usize,
+        pxrange: u32,
+        is_outer_glow: bool,
+        cur_off: u32,
+        is_cw: bool
+    ) -> Vec<u8> {
+        let result_arcs: CellInfo = bitcode::deserialize(result_arcs).unwrap();
+        // 只用到布局相关的字段，不需要轮廓端点
+        let outline = OutlineInfo {
+            char: ' ',
+            endpoints: vec![],
+            bbox: vec![],
+            advance,
+            units_per_em,
+            extents: extents.to_vec(),
+            svg_paths: vec![],
+            is_cw,
+            max_error: 0.0,
+            fit_failed: false,
+        };
+        let mut info = outline.compute_sdf_tex_rect(result_arcs, tex_size, pxrange, is_outer_glow, cur_off);
+        info.tex_info.advance = advance as f32 / units_per_em as f32;
+        bitcode::serialize(&info).unwrap()
+    }
+
+    /// 通过 wasm 绑定计算字符的非方形布局信息
+    ///
+    /// # 返回
+    /// * `Vec<f32>` - 与`compute_layout_of_wasm`相同，末尾追加纹理宽度和高度
+    pub fn compute_layout_rect_of_wasm(
+        extents: &[f32],
+        units_per_em: u16,
+        tex_size: usize,
+        pxrange: u32,
+        cur_off: u32,
+    ) -> Vec<f32> {
+        let LayoutInfo {
+            mut plane_bounds,
+            mut atlas_bounds,
+            mut extents,
+            distance,
+            tex_size,
+            tex_width,
+            tex_height,
+        } = compute_layout_rect(extents, tex_size, pxrange, units_per_em, cur_off, false);
+        let mut res = Vec::with_capacity(16);
+        res.append(&mut plane_bounds);
+        res.append(&mut atlas_bounds);
+        res.append(&mut extents);
+        res.push(distance);
+        res.push(tex_size as f32);
+        res.push(tex_width as f32);
+        res.push(tex_height as f32);
+        res
+    }
 
     /// 通过 wasm 绑定计算字符的布局信息，并返回序列化后的字节数组
     ///
@@ -335,6 +887,7 @@ impl OutlineInfo {
             mut extents,
             distance,
             tex_size,
+            ..
         } = compute_layout(extents, tex_size, pxrange, units_per_em, cur_off, false);
         let mut res = Vec::with_capacity(14);
         res.append(&mut plane_bounds);
@@ -772,6 +1325,45 @@ impl ttf_parser::OutlineBuilder for GlyphVisitor {
 }
 
 
+/// 描边的对齐方式
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrokeAlign {
+    /// 描边在轮廓内侧
+    Inside,
+    /// 描边以轮廓为中心
+    Center,
+    /// 描边在轮廓外侧
+    Outside,
+}
+
+/// 描边参数，宽度与sdf同单位
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Stroke {
+    pub width: f32,
+    pub align: StrokeAlign,
+}
+
+impl Stroke {
+    pub fn new(width: f32, align: StrokeAlign) -> Self {
+        Self { width, align }
+    }
+
+    /// 将到轮廓的有符号距离（内部为负）转换为到描边带的有符号距离
+    ///
+    /// 宽度为0的居中描边即为距离的绝对值，开放路径使用该方式
+    pub fn distance(&self, sdf: f32) -> f32 {
+        let half = self.width * 0.5;
+        // 描边带中心线相对轮廓的偏移
+        let center = match self.align {
+            StrokeAlign::Inside => -half,
+            StrokeAlign::Center => 0.0,
+            StrokeAlign::Outside => half,
+        };
+        (sdf - center).abs() - half
+    }
+}
+
 /// 将输入的矢量数据转换为纹理表示的SDF格式
 ///
 /// # 参数
@@ -779,8 +1371,9 @@ impl ttf_parser::OutlineBuilder for GlyphVisitor {
 /// * `arcs_info` - 每个网格点对应的弧段索引及其在矢量路径中所占据的位置
 /// * `extents` - 矢量形状的包围盒
 /// * `tex_size` - 生成的纹理宽度和高度（二维纹理，方形纹理，边长为tex_size）
-/// * `distance` - 在该距离内，alpha值衰减为0的点（边缘范围的截止值）
-/// * `width` - SVG转换中的线宽（未实现，保留为None）
+/// * `inner_distance` - 内部（sdf < 0）的距离范围，在该距离处alpha值达到最大
+/// * `outer_distance` - 外部（sdf > 0）的距离范围，在该距离处alpha值衰减为0
+/// * `stroke` - 描边参数，非空时计算到描边带的距离（开放路径传宽度为0的居中描边）
 /// * `is_outer_glow` - 是否应用外发光效果（闪烁效果）
 /// * `is_svg` - 是否作为SVG路径进行处理（影响坐标变换的方向）
 /// * `is_reverse` - 是否反转颜色通道（区分前景与背景）
@@ -791,8 +1384,46 @@ pub fn encode_sdf(
     arcs_info: Vec<(Vec<usize>, Aabb)>,
     extents: &Aabb,
     tex_size: usize,
-    distance: f32, // sdf在这个值上alpha 衰减为 0
-    width: Option<f32>,
+    inner_distance: f32,
+    outer_distance: f32, // sdf在这个值上alpha 衰减为 0
+    stroke: Option<Stroke>,
+    is_outer_glow: bool,
+    is_svg: bool,
+    is_reverse: Option<bool>,
+) -> Vec<u8> {
+    encode_sdf_rect(
+        global_arcs,
+        arcs_info,
+        extents,
+        tex_size,
+        tex_size,
+        inner_distance,
+        outer_distance,
+        stroke,
+        is_outer_glow,
+        is_svg,
+        is_reverse,
+    )
+}
+
+/// 将输入的矢量数据转换为非方形的SDF纹理
+///
+/// # 参数
+/// * `tex_width` - 纹理宽度
+/// * `tex_height` - 纹理高度，`extents`的宽高比应与纹理宽高比一致（见`compute_layout_rect`）
+///
+/// 其余参数与`encode_sdf`相同
+/// # 返回值
+/// 生成的SDF纹理，大小为`tex_width * tex_height`
+pub fn encode_sdf_rect(
+    global_arcs: &Vec<Arc>,
+    arcs_info: Vec<(Vec<usize>, Aabb)>,
+    extents: &Aabb,
+    tex_width: usize,
+    tex_height: usize,
+    inner_distance: f32,
+    outer_distance: f32, // sdf在这个值上alpha 衰减为 0
+    stroke: Option<Stroke>,
     is_outer_glow: bool,
     is_svg: bool,
     is_reverse: Option<bool>,
@@ -803,10 +1434,10 @@ pub fn encode_sdf(
     // 假设高度不为零，暂未处理高度为零的情况
 
     // 计算每单元在包围盒中的尺寸宽度，该值为纹理单位的缩放因子
-    let unit_d = glyph_width / tex_size as f32; // 计算每单元宽度
+    let unit_d = glyph_width / tex_width as f32; // 计算每单元宽度
 
     // 初始化所有纹理点的值为0
-    let mut data = vec![0; tex_size * tex_size]; // 创建一个一维数组用于存储最终的纹理数据
+    let mut data = vec![0; tex_width * tex_height]; // 创建一个一维数组用于存储最终的纹理数据
 
     // 遍历每个网格点（cell），每个cell对应一个弧段列表，以及该cell在矢量形状包围盒中的所在区域
     for (near_arcs, cell) in arcs_info {  // 遍历每个预处理好的单元格
@@ -828,11 +1459,11 @@ pub fn encode_sdf(
 
             let mut end_x = end.x / unit_d;
             end_x = (end_x * 10000.0).round() * 0.0001;
-            let end_x = end_x.round() as usize;
+            let end_x = (end_x.round() as usize).min(tex_width);
 
             let mut end_y = end.y / unit_d;
             end_y = (end_y * 10000.0).round() * 0.0001;
-            let end_y = end_y.round() as usize;
+            let end_y = (end_y.round() as usize).min(tex_height);
 
             // 遍历该单元格在纹理中的对应区域，每个(i,j)点即为纹理中的一个点，对应的2D坐标i,j转换为线性数组索引i + j * tex_size
             for i in begin_x..end_x {  // 纹理x轴方向遍历
@@ -848,8 +1479,9 @@ pub fn encode_sdf(
                         global_arcs,
                         p,
                         &near_arcs,
-                        distance,
-                        width,
+                        inner_distance,
+                        outer_distance,
+                        stroke,
                         is_outer_glow,
                         is_reverse,
                     );
@@ -857,10 +1489,10 @@ pub fn encode_sdf(
                     // 根据是否是_svg模式，调整点p在数据数组中的索引。_svg模式则不需要颠倒y轴，否则颠倒y轴以适应纹理坐标系
                     if is_svg {
                         // 对SVG不存在颠倒，索引i,j直接访问
-                        data[j * tex_size + i] = r.0;
+                        data[j * tex_width + i] = r.0;
                     } else {
                         // 非-svg模式下，颠倒y轴，将纹理的y轴坐标从下往上存储，即(y) -> (tex_size - 1 - y)
-                        data[(tex_size - j - 1) * tex_size + i] = r.0;
+                        data[(tex_height - j - 1) * tex_width + i] = r.0;
                     }
                 }
             }
@@ -876,8 +1508,9 @@ pub fn encode_sdf(
 /// * `global_arcs` - 全局的弧段列表，包含整个矢量形状的各个弧线段信息。
 /// * `p` - 当前需要计算SDF的点的位置。
 /// * `near_arcs` - 当前单元内的近邻弧段列表。用于快速计算该点的SDF。
-/// * `distance` - SDF衰减距离，超过这个距离的点，其alpha值为0。
-/// * `width` - 轮廓宽度，如果非空，则将宽度中心设定为0.5，否则将取具体的距离。
+/// * `inner_distance` - 内部的SDF衰减距离，与`outer_distance`相等时内外对称。
+/// * `outer_distance` - 外部的SDF衰减距离，超过这个距离的点，其alpha值为0。
+/// * `stroke` - 描边参数，如果非空，则计算到描边带的距离，否则为到轮廓的距离。
 /// * `is_outer_glow` - 是否应用外发光效果，主要用于闪烁效果处理。
 /// * `is_reverse` - 是否反转颜色通道，用于区分前景与背景颜色。
 ///
@@ -890,8 +1523,9 @@ pub fn compute_sdf2(
     global_arcs: &Vec<Arc>,         // v作为arc的全局集合输入接口模式不影响内部实现，无需改变变量类型，否则会导致编译错误。但此处是智能指针，应该可以处理。
     p: Point,                       // 点p。能发现它是参数，可以通过显式传递进行计算。
     near_arcs: &Vec<usize>,         // 当前单元格内的近邻arc索引列表，用于快速计算。
-    distance: f32,                  // 衰减距离。
-    width: Option<f32>,             // 宽度参数，可能影响中心点的处理。
+    inner_distance: f32,            // 内部衰减距离。
+    outer_distance: f32,            // 外部衰减距离。
+    stroke: Option<Stroke>,         // 描边参数。
     is_outer_glow: bool,            // 是否外发光效果。
     is_reverse: Option<bool>,       // 是否反转颜色通道。
 ) -> (u8, f32, f32) {
@@ -904,16 +1538,17 @@ pub fn compute_sdf2(
             sdf = -sdf;
         }
     }
-    if let Some(_) = width {
-        sdf = sdf.abs(); // - (width * 0.5);
+    if let Some(stroke) = stroke {
+        sdf = stroke.distance(sdf);
     }
 
     if is_outer_glow {
-        let sdf2 = (1.0 - (sdf / distance)).powf(1.99);
+        let sdf2 = (1.0 - (sdf / outer_distance)).powf(1.99);
         return ((sdf2 * 255.0).round() as u8, sdf, sdf2);
         // log::debug!("{:?}", (radius, sdf));
     } else {
-        let sdf2 = sdf / distance;
+        // 边缘为127，内外分别按各自的范围映射
+        let sdf2 = if sdf > 0.0 { sdf / outer_distance } else { sdf / inner_distance };
         let a = ((1.0 - sdf2) * 127.0).round() as u8;
         // log::debug!("sdf: {:?}", (sdf, a, distance));
         return (a, sdf, sdf2);
@@ -928,6 +1563,10 @@ pub struct LayoutInfo {
     pub extents: Vec<f32>,
     pub distance: f32,
     pub tex_size: u32,
+    /// 纹理宽度（包含cur_off）
+    pub tex_width: u32,
+    /// 纹理高度（包含cur_off）
+    pub tex_height: u32,
 }
 
 // #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
@@ -940,7 +1579,6 @@ pub(crate) fn compute_layout(
     is_svg: bool,
 ) -> LayoutInfo {
     // 创建一个aabb包围盒用于存储矢量图的几何范围。
-    // map 无序导致每次计算的数据不一样
     let mut extents2 = Aabb::new(
         Point::new(extents[0], extents[1]),
         Point::new(extents[2], extents[3]),
@@ -1016,6 +1654,141 @@ pub(crate) fn compute_layout(
         ],
         distance,
         tex_size: tex_size as u32,
+        tex_width: tex_size as u32,
+        tex_height: tex_size as u32,
+    }
+}
+
+/// 计算非方形的布局信息
+///
+/// 与`compute_layout`不同，不会把包围盒补齐成正方形，纹理宽高按包围盒的宽高比分别计算，
+/// `atlas_bounds`为字形在纹理中所占的紧凑矩形
+///
+/// # 参数
+/// * `extents` - 包围盒 [min_x, min_y, max_x, max_y]
+/// * `tex_size` - 较长边的纹理大小（不含cur_off）
+/// * `pxrange` - 像素范围
+/// * `units_per_em` - 每em单位的数量
+/// * `cur_off` - 纹理四周的偏移像素
+/// * `is_svg` - 是否为svg（svg纹理y轴不翻转）
+///
+/// # 返回
+/// * `LayoutInfo` - `tex_width`和`tex_height`分别为纹理的宽高，`tex_size`为两者中较大的值
+pub(crate) fn compute_layout_rect(
+    extents: &[f32],
+    tex_size: usize,
+    pxrange: u32,
+    units_per_em: u16,
+    cur_off: u32,
+    is_svg: bool,
+) -> LayoutInfo {
+    // 像素大小由较长边决定，保证与方形布局的精度一致
+    let px_distance = (extents[2] - extents[0]).max(extents[3] - extents[1]) / tex_size as f32;
+    layout_rect(extents, px_distance, pxrange, units_per_em, cur_off, is_svg)
+}
+
+/// 按固定的每em像素数计算非方形的布局信息
+///
+/// 所有字符的像素/em都相同，纹理宽高随字形大小变化，适合导出BMFont这类所有字符共用一个字号的格式
+///
+/// # 参数
+/// * `extents` - 包围盒 [min_x, min_y, max_x, max_y]，每em为`SCALE`
+/// * `px_per_em` - 每em对应的像素数
+/// * `pxrange` - 像素范围
+/// * `units_per_em` - 每em单位的数量
+/// * `cur_off` - 纹理四周的偏移像素
+/// * `is_svg` - 是否为svg（svg纹理y轴不翻转）
+///
+/// # 返回
+/// * `LayoutInfo` - 同`compute_layout_rect`
+pub(crate) fn compute_layout_px_per_em(
+    extents: &[f32],
+    px_per_em: f32,
+    pxrange: u32,
+    units_per_em: u16,
+    cur_off: u32,
+    is_svg: bool,
+) -> LayoutInfo {
+    layout_rect(extents, SCALE / px_per_em, pxrange, units_per_em, cur_off, is_svg)
+}
+
+// 按每像素对应的距离计算非方形布局
+fn layout_rect(
+    extents: &[f32],
+    px_distance: f32,
+    pxrange: u32,
+    units_per_em: u16,
+    cur_off: u32,
+    is_svg: bool,
+) -> LayoutInfo {
+    let mut extents2 = Aabb::new(
+        Point::new(extents[0], extents[1]),
+        Point::new(extents[2], extents[3]),
+    );
+    let extents_w = extents2.width();
+    let extents_h = extents2.height();
+    let scale = 1.0 / units_per_em as f32;
+    let plane_bounds = extents2.scaled(&Vector::new(scale, scale));
+    let distance = px_distance * pxrange as f32;
+
+    // 短边按像素向上取整，包围盒补齐到整像素
+    let tex_w = ((extents_w / px_distance - 0.0001).ceil() as usize).max(1);
+    let tex_h = ((extents_h / px_distance - 0.0001).ceil() as usize).max(1);
+    let pad_w = tex_w as f32 * px_distance - extents_w;
+    let pad_h = tex_h as f32 * px_distance - extents_h;
+    extents2.maxs.x += pad_w;
+    extents2.maxs.y += pad_h;
+
+    let expand = px_distance * cur_off as f32;
+    extents2.mins.x -= expand;
+    extents2.mins.y -= expand;
+    extents2.maxs.x += expand;
+    extents2.maxs.y += expand;
+
+    let tex_width = tex_w + (cur_off * 2) as usize;
+    let tex_height = tex_h + (cur_off * 2) as usize;
+
+    // 紧凑矩形，补齐的部分不计入
+    let mut atlas_bounds = Aabb::new_invalid();
+    atlas_bounds.mins.x = cur_off as f32;
+    atlas_bounds.maxs.x = cur_off as f32 + extents_w / px_distance;
+    if is_svg {
+        atlas_bounds.mins.y = cur_off as f32;
+        atlas_bounds.maxs.y = cur_off as f32 + extents_h / px_distance;
+    } else {
+        // 非svg纹理y轴翻转，补齐的部分在纹理顶部
+        atlas_bounds.mins.y = cur_off as f32 + pad_h / px_distance;
+        atlas_bounds.maxs.y = (cur_off as usize + tex_h) as f32;
+    }
+
+    log::debug!(
+        "plane_bounds: {:?}, atlas_bounds: {:?}, tex_width: {}, tex_height: {}",
+        plane_bounds, atlas_bounds, tex_width, tex_height
+    );
+
+    LayoutInfo {
+        plane_bounds: vec![
+            plane_bounds.mins.x,
+            plane_bounds.mins.y,
+            plane_bounds.maxs.x,
+            plane_bounds.maxs.y,
+        ],
+        atlas_bounds: vec![
+            atlas_bounds.mins.x,
+            atlas_bounds.mins.y,
+            atlas_bounds.maxs.x,
+            atlas_bounds.maxs.y,
+        ],
+        extents: vec![
+            extents2.mins.x,
+            extents2.mins.y,
+            extents2.maxs.x,
+            extents2.maxs.y,
+        ],
+        distance,
+        tex_size: tex_width.max(tex_height) as u32,
+        tex_width: tex_width as u32,
+        tex_height: tex_height as u32,
     }
 }
 
@@ -1049,6 +1822,18 @@ pub fn compute_cell_range(mut bbox: Aabb, scale: f32) -> Aabb {
     bbox
 }
 
+/// 计算非方形的格子范围，不补齐成正方形，四周按较长边扩展
+pub fn compute_cell_range_rect(mut bbox: Aabb, scale: f32) -> Aabb {
+    let scale = scale * 0.5;
+    let extents = scale * bbox.width().max(bbox.height());
+    bbox.mins.x -= extents;
+    bbox.mins.y -= extents;
+    bbox.maxs.x += extents;
+    bbox.maxs.y += extents;
+
+    bbox
+}
+
 pub fn to_arc_cmds(endpoints: &Vec<ArcEndpoint>) -> (Vec<Vec<String>>, Vec<[f32; 2]>) {
     // 初始化命令数组和点列表以存储处理结果
     let mut _cmd = vec![];
@@ -1243,6 +2028,24 @@ pub struct CellInfo {
 
 impl CellInfo {
     pub fn encode_blob_arc(&self) -> BlobArc {
+        self.encode_blob_arc_with_format(DataFormat::Rgba8)
+    }
+
+    /// 圆弧转为三次贝塞尔路径
+    pub fn to_bez_path(&self) -> kurbo::BezPath {
+        arcs_to_bez_path(&self.arcs)
+    }
+
+    /// 圆弧和细分的格子写成SVG文档
+    pub fn to_svg(&self, options: &SvgExportOptions) -> String {
+        cell_info_to_svg(self, options)
+    }
+
+    /// 展开成最小格子的均匀网格，数据纹理按指定格式编码
+    ///
+    /// # 参数
+    /// * `data_format` - 数据纹理的编码格式，`DataFormat::Rgba16`时端点坐标每轴16位，只能用`BlobArc::try_encode_tex`编码成单独的纹理
+    pub fn encode_blob_arc_with_format(&self, data_format: DataFormat) -> BlobArc {
         let extents = &self.extents;
 
         let result_arcs = &self.info;
@@ -1291,7 +2094,7 @@ impl CellInfo {
         let c = extents.center();
         let unit = glyph_width.max(glyph_height);
 
-        let mut map = HashMap::new();
+        let mut map = BTreeMap::new();
         // 二分计算时，个格子的大小会不一样
         // 统一以最小格子细分
         for (near_arcs, cell) in result_arcs {
@@ -1335,18 +2138,8 @@ impl CellInfo {
                 let end = &near_endpoints[1];
 
                 let mut line = Line::from_points(
-                    snap(
-                        &Point::new(start.p[0], start.p[1]),
-                        &extents,
-                        glyph_width,
-                        glyph_height,
-                    ),
-                    snap(
-                        &Point::new(end.p[0], end.p[1]),
-                        &extents,
-                        glyph_width,
-                        glyph_height,
-                    ),
+                    data_format.snap(&Point::new(start.p[0], start.p[1]), extents),
+                    data_format.snap(&Point::new(end.p[0], end.p[1]), extents),
                 );
                 // Shader的最后 要加回去
                 line.c -= line.n.dot(&c.into_vector());
@@ -1354,7 +2147,7 @@ impl CellInfo {
                 line.c /= unit;
 
                 let line_key = near_endpoints[0].get_line_key(&near_endpoints[1]);
-                let le = line_encode(line);
+                let le = data_format.encode_line(line);
 
                 let mut line_data = ArcEndpoint::new(0.0, 0.0, 0.0);
                 line_data.line_key = Some(line_key);
@@ -1411,13 +2204,12 @@ impl CellInfo {
                 }
             }
             let key = data[begin_y][begin_x].get_key();
-            let ptr: *const UnitArc = &data[begin_y][begin_x];
-            // 使用map 去重每个格子的数据纹理
-            map.insert(key, ptr as u64);
+            // 使用map 去重每个格子的数据纹理，记录代表格子的位置
+            map.insert(key, (begin_y, begin_x));
         }
         let [min_sdf, max_sdf] = travel_data(&data);
 
-        BlobArc {
+        let mut blob = BlobArc {
             min_sdf,
             max_sdf,
             cell_size: min_width,
@@ -1428,7 +2220,11 @@ impl CellInfo {
             avg_fetch_achieved: 0.0,
             endpoints: vec![],
             data_tex_map: map,
-        }
+            data_format,
+        };
+        blob.assign_data_offsets();
+        blob.avg_fetch_achieved = blob.avg_fetch();
+        blob
     }
 }
 
@@ -1437,7 +2233,8 @@ impl Serialize for CellInfo {
     where
         S: serde::Serializer,
     {
-        let unit_size = self.extents.width() / 32.0;
+        // 非方形范围时，x、y方向的单位大小不同
+        let unit_size = Vector::new(self.extents.width() / 32.0, self.extents.height() / 32.0);
         let start_point = self.extents.mins;
 
         let mut s = serializer.serialize_struct("CellInfo", 9)?;
@@ -1449,9 +2246,9 @@ impl Serialize for CellInfo {
 
         let mut info = Vec::with_capacity(self.info.len());
         for (arcs, ab) in &self.info {
-            let offset = (ab.mins - start_point) / unit_size;
-            let w = ab.width() / unit_size;
-            let h = ab.height() / unit_size;
+            let offset = (ab.mins - start_point).component_div(&unit_size);
+            let w = ab.width() / unit_size.x;
+            let h = ab.height() / unit_size.y;
             let mut temp = Vec::with_capacity(arcs.len());
             for arc in arcs {
                 temp.push(*arc as u16);
@@ -1512,16 +2309,17 @@ impl<'de> Deserialize<'de> for CellInfo {
                     .ok_or_else(|| de::Error::invalid_length(5, &self))?;
 
                 let extents = Aabb::new(Point::new(mins_x, mins_y), Point::new(maxs_x, maxs_y1));
-                let unit_size = extents.width() / 32.0;
+                let unit_w = extents.width() / 32.0;
+                let unit_h = extents.height() / 32.0;
 
                 let mut info = Vec::with_capacity(src_info.len());
                 for (indexs, offset_x, offset_y, w, h) in src_info {
                     let min = Point::new(
-                        extents.mins.x + unit_size * offset_x as f32,
-                        extents.mins.y + unit_size * offset_y as f32,
+                        extents.mins.x + unit_w * offset_x as f32,
+                        extents.mins.y + unit_h * offset_y as f32,
                     );
                     let max =
-                        Point::new(min.x + unit_size * w as f32, min.y + unit_size * h as f32);
+                        Point::new(min.x + unit_w * w as f32, min.y + unit_h * h as f32);
                     let mut arc_index = Vec::with_capacity(indexs.len());
                     for i in indexs {
                         arc_index.push(i as usize);